@@ -1,78 +1,807 @@
-use anyhow::Result;
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, Local, NaiveDate, Utc};
+use clap::{
+    builder::{PossibleValuesParser, TypedValueParser},
+    Args, CommandFactory, Parser, Subcommand,
+};
+use clap_complete::{env::Shells, ArgValueCandidates, CompleteEnv, CompletionCandidate};
 use jankloada_lib::{
+    conflicts::ConflictReport,
     data_manager::DataManager,
-    mod_data::{ModFileDTO, ModProfile},
+    diff::ModListDiff,
+    history::SnapshotOrigin,
+    mod_data::{MatchRule, ModFileDTO, ModProfile, ModUUID},
+    steam::read_workshop_items,
+};
+use output::{
+    CandidateRow, ChoiceRow, ConflictRow, DiffRow, ModFileRow, ModOrder, ModRow, OutputFormat,
+    ProfileModRow, ProfileRow, ShareRow, SnapshotRow,
 };
-use std::env;
+use std::{env, io, path::PathBuf};
 
-fn main() -> Result<()> {
+mod output;
+
+/// Saves and applies Total War: Warhammer 3 mod profiles.
+#[derive(Parser)]
+#[command(version, about)]
+struct Cli {
+    /// Use this mod file instead of the auto-detected one
+    #[arg(long, global = true, value_name = "PATH")]
+    mod_file: Option<PathBuf>,
+
+    /// Read settings and profiles from this dir instead of the default one
+    #[arg(long, global = true, value_name = "DIR")]
+    data_dir: Option<PathBuf>,
+
+    /// Only list mods for this game [default: from settings, or warhammer3]
+    #[arg(long, global = true)]
+    game: Option<String>,
+
+    /// Loosest way to match profile mods to installed ones [default: from settings, or packfile]
+    #[arg(
+        long = "match",
+        global = true,
+        value_name = "RULE",
+        value_parser = PossibleValuesParser::new(["uuid", "workshop_id", "packfile"])
+            .map(|s| match s.as_str() {
+                "uuid" => MatchRule::Uuid,
+                "workshop_id" => MatchRule::WorkshopId,
+                _ => MatchRule::Packfile,
+            }),
+    )]
+    match_rule: Option<MatchRule>,
+
+    /// How to print results [default: table]; json and csv follow the schema in the README
+    #[arg(long, global = true, value_enum)]
+    output: Option<OutputFormat>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Dump the mod file as the launcher wrote it, or list its mods with --output or a sort/filter
+    Print {
+        /// Dump the mod file even with --output
+        #[arg(long)]
+        raw: bool,
+        #[command(flatten)]
+        view: ModView,
+    },
+    /// List active mods
+    Current {
+        #[command(flatten)]
+        view: ModView,
+    },
+    /// List mods whose pack file is gone
+    Missing,
+    /// Save the active mods as a profile
+    Save {
+        #[arg(add = ArgValueCandidates::new(profile_names))]
+        name: String,
+    },
+    /// List saved profiles
+    List,
+    /// Change a profile's description, author, tags or notes
+    Edit {
+        #[arg(add = ArgValueCandidates::new(profile_names))]
+        name: String,
+        /// Empty to remove it
+        #[arg(long)]
+        description: Option<String>,
+        /// Empty to remove it
+        #[arg(long)]
+        author: Option<String>,
+        /// Add a tag, can be repeated
+        #[arg(long, value_name = "TAG")]
+        tag: Vec<String>,
+        /// Remove a tag, can be repeated
+        #[arg(long, value_name = "TAG")]
+        untag: Vec<String>,
+        /// Note on a mod in the profile, by uuid, workshop id or pack file;
+        /// an empty NOTE removes it. Can be repeated
+        #[arg(long, value_name = "MOD=NOTE")]
+        note: Vec<String>,
+    },
+    /// Show the mods in a profile
+    Show {
+        /// Defaults to the preferred profile from the settings
+        #[arg(add = ArgValueCandidates::new(profile_names))]
+        name: Option<String>,
+    },
+    /// Print a profile as a share code, to paste to your mates
+    Share {
+        /// Defaults to the preferred profile from the settings
+        #[arg(add = ArgValueCandidates::new(profile_names))]
+        name: Option<String>,
+    },
+    /// Save a profile from a share code
+    ImportCode {
+        /// Read from stdin if left out
+        code: Option<String>,
+        /// Save it under this name instead of the one in the code
+        #[arg(long)]
+        name: Option<String>,
+        /// Overwrite a profile with the same name
+        #[arg(long)]
+        force: bool,
+    },
+    /// Save the active mods in someone else's mod file as a profile
+    ImportFile {
+        /// Their moddata.dat
+        path: PathBuf,
+        #[arg(add = ArgValueCandidates::new(profile_names))]
+        name: String,
+        /// Save without asking
+        #[arg(long, short)]
+        yes: bool,
+    },
+    /// Make the mod file match a profile
+    Apply {
+        /// Defaults to the preferred profile from the settings
+        #[arg(add = ArgValueCandidates::new(profile_names))]
+        name: Option<String>,
+        /// Only show what would change
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Activate mods
+    Enable {
+        /// A uuid, name, pack file, or part of a name
+        #[arg(required = true, add = ArgValueCandidates::new(mod_names))]
+        mods: Vec<String>,
+    },
+    /// Deactivate mods
+    Disable {
+        /// A uuid, name, pack file, or part of a name
+        #[arg(required = true, add = ArgValueCandidates::new(mod_names))]
+        mods: Vec<String>,
+    },
+    /// Activate inactive mods and deactivate active ones
+    Toggle {
+        /// A uuid, name, pack file, or part of a name
+        #[arg(required = true, add = ArgValueCandidates::new(mod_names))]
+        mods: Vec<String>,
+    },
+    /// Change where a mod is in the load order
+    Move {
+        /// A uuid, name, pack file, or part of a name
+        #[arg(add = ArgValueCandidates::new(mod_names))]
+        name: String,
+        #[command(flatten)]
+        to: MoveTarget,
+    },
+    /// List files that more than one active mod ships, and whose copy wins
+    Conflicts,
+    /// Remove mods whose pack file is gone from the mod file
+    Prune {
+        /// Only show what would be removed
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Show everywhere the mod file was looked for
+    Locate,
+    /// List every mod file that was found
+    Files,
+    /// Stick to one of the mod files from `files`
+    Choose {
+        /// Number from `files`, a path, or "auto" to go back to auto-detection
+        choice: String,
+    },
+    /// List the mod lists written so far
+    History,
+    /// Go back to the mod list before the last change
+    Undo,
+    /// Bring back a mod list from `history`
+    Restore { id: u64 },
+    /// Print a script that sets up tab completion, e.g. `source <(jankloada-cli completions bash)`
+    Completions {
+        #[arg(value_parser = Shells::builtins().names().collect::<Vec<_>>())]
+        shell: String,
+    },
+}
+
+/// Sorting and filtering for mod lists.
+#[derive(Args)]
+struct ModView {
+    /// What to sort by [default: position]
+    #[arg(long, value_enum)]
+    sort: Option<ModOrder>,
+    /// Only list mods from the Steam workshop
+    #[arg(long)]
+    workshop: bool,
+    /// Only list workshop mods updated on or after this day (YYYY-MM-DD)
+    #[arg(long, value_name = "DATE")]
+    updated_since: Option<NaiveDate>,
+}
+
+impl ModView {
+    fn apply(&self, mut rows: Vec<ModRow>) -> Vec<ModRow> {
+        rows.retain(|r| !self.workshop || r.workshop_id.is_some());
+        if let Some(since) = self.updated_since {
+            rows.retain(|r| r.updated.is_some_and(|u| u.date_naive() >= since));
+        }
+        self.sort.unwrap_or(ModOrder::Position).sort(&mut rows);
+        rows
+    }
+
+    fn is_set(&self) -> bool {
+        self.sort.is_some() || self.workshop || self.updated_since.is_some()
+    }
+}
+
+#[derive(Args)]
+#[group(required = true, multiple = false)]
+struct MoveTarget {
+    /// Right before this mod
+    #[arg(long, value_name = "MOD", add = ArgValueCandidates::new(mod_names))]
+    before: Option<String>,
+    /// Right after this mod
+    #[arg(long, value_name = "MOD", add = ArgValueCandidates::new(mod_names))]
+    after: Option<String>,
+    /// First in the load order
+    #[arg(long)]
+    top: bool,
+    /// Last in the load order
+    #[arg(long)]
+    bottom: bool,
+}
+
+/// Flags, the env and settings win over the defaults, in that order.
+fn data_manager(data_dir: Option<PathBuf>, mod_file: Option<PathBuf>) -> Result<DataManager> {
+    let mut builder = DataManager::builder();
+    if let Some(dir) = data_dir {
+        builder = builder.data_dir(dir);
+    }
+    if let Some(path) = mod_file {
+        builder = builder.mod_file(path);
+    }
+    builder.env().build()
+}
+
+/// The value of `flag` on the command line being completed, if it's there yet.
+fn completing_flag(flag: &str) -> Option<String> {
     let args: Vec<String> = env::args().collect();
-    let arg_cmd = args.get(1);
-    let arg_profile = args.get(2);
+    args.iter()
+        .enumerate()
+        .find_map(|(i, arg)| match arg.strip_prefix(flag)? {
+            "" => args.get(i + 1).cloned(),
+            value => value.strip_prefix('=').map(str::to_string),
+        })
+}
+
+/// Set up like `main` would for the command line being completed.
+fn completing_data_manager() -> Result<DataManager> {
+    data_manager(
+        completing_flag("--data-dir").map(PathBuf::from),
+        completing_flag("--mod-file").map(PathBuf::from),
+    )
+}
+
+fn mod_names() -> Vec<CompletionCandidate> {
+    completing_data_manager()
+        .and_then(|d| {
+            let game = completing_flag("--game").unwrap_or_else(|| d.settings().game().to_string());
+            Ok(d.load_mod_file()?
+                .mods()
+                .iter()
+                .filter(|m| m.game == game)
+                .map(|m| CompletionCandidate::new(&m.name))
+                .collect())
+        })
+        .unwrap_or_default()
+}
+
+fn profile_names() -> Vec<CompletionCandidate> {
+    completing_data_manager()
+        .and_then(|d| d.list_profiles())
+        .unwrap_or_default()
+        .into_iter()
+        .map(CompletionCandidate::new)
+        .collect()
+}
+
+/// Asks a yes/no question on the terminal; anything but yes is no.
+fn confirm(question: &str) -> Result<bool> {
+    eprint!("{question} [y/N] ");
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
 
-    let data_manager = DataManager::new()?;
+/// Looks up what the last write to the mod file was recorded as.
+fn last_snapshot(data_manager: &DataManager) -> Result<Vec<SnapshotRow>> {
+    Ok(data_manager
+        .history()?
+        .last()
+        .map(SnapshotRow::from)
+        .into_iter()
+        .collect())
+}
+
+/// Sets every mod for `game` matching `queries` to `state(active)` and saves the mod file.
+fn set_active(
+    data_manager: &DataManager,
+    format: OutputFormat,
+    queries: &[String],
+    game: &str,
+    state: impl Fn(bool) -> bool,
+) -> Result<()> {
+    let mut mod_list = data_manager.load_mod_file()?;
+    let mut indices = Vec::new();
+    for query in queries {
+        let i = mod_list.find_mod(query, game)?;
+        // Toggling the same mod twice would do nothing
+        if !indices.contains(&i) {
+            indices.push(i);
+        }
+    }
+    for &i in &indices {
+        let active = mod_list.mods()[i].active;
+        mod_list.set_mod_active_state(i, state(active))?;
+    }
+    let workshop = read_workshop_items(&mod_list);
+    let rows: Vec<_> = indices
+        .iter()
+        .map(|&i| ModRow::new(i + 1, mod_list.mods()[i], &workshop))
+        .collect();
+    data_manager.save_to_mod_file(mod_list, SnapshotOrigin::Edit)?;
+    output::print(format, &rows, |rows| {
+        for m in rows {
+            let verb = if m.active { "Enabled" } else { "Disabled" };
+            println!("{verb} {}", m.name)
+        }
+    })
+}
+
+fn main() -> Result<()> {
+    CompleteEnv::with_factory(Cli::command).complete();
+    let cli = Cli::parse();
 
-    let mod_list = data_manager.load_mod_file()?;
+    let mut data_manager = data_manager(cli.data_dir, cli.mod_file)?;
+    let game = cli
+        .game
+        .unwrap_or_else(|| data_manager.settings().game().to_string());
+    let loosest = cli
+        .match_rule
+        .unwrap_or_else(|| data_manager.settings().match_strictness());
+    let preferred_profile = data_manager.settings().preferred_profile.clone();
+    let profile_or_preferred = |name: Option<String>| {
+        name.or(preferred_profile.clone())
+            .ok_or_else(|| anyhow!("Missing profile name, and no preferred profile is set"))
+    };
 
-    if let Some(cmd) = arg_cmd {
-        match cmd.as_str() {
-            "print" => println!(
+    let format = cli.output.unwrap_or(OutputFormat::Table);
+    match cli.command {
+        // Without --output, print dumps the mod file like it always has
+        Command::Print { raw, view } if raw || (cli.output.is_none() && !view.is_set()) => {
+            println!(
                 "{}",
-                serde_json::to_string_pretty::<ModFileDTO>(&mod_list.into())?
-            ),
-            "current" => {
-                let active_mods = mod_list.get_active();
-                for (i, n) in active_mods.iter().enumerate() {
+                serde_json::to_string_pretty::<ModFileDTO>(&data_manager.load_mod_file()?.into())?
+            )
+        }
+        Command::Print { view, .. } => {
+            let mod_list = data_manager.load_mod_file()?;
+            let rows = view.apply(ModRow::from_mod_list(&mod_list, |_| true));
+            output::print(format, &rows, |rows| {
+                for m in rows {
+                    let active = if m.active { "x" } else { " " };
+                    let updated = m
+                        .updated
+                        .map(|u| {
+                            format!(", updated {}", u.with_timezone(&Local).format("%Y-%m-%d"))
+                        })
+                        .unwrap_or_default();
+                    println!(
+                        "{} [{active}] {} ({}{updated})",
+                        m.position, m.name, m.packfile
+                    )
+                }
+            })?
+        }
+        Command::Current { view } => {
+            let mod_list = data_manager.load_mod_file()?;
+            let rows = view.apply(ModRow::from_mod_list(&mod_list, |m| {
+                m.active && m.game == game
+            }));
+            output::print(format, &rows, |rows| {
+                for (i, n) in rows.iter().enumerate() {
                     println!("{i} - {}", n.name)
                 }
-            }
-            "missing" => {
-                let missing_mods = mod_list.get_missing();
-                for (i, n) in missing_mods.iter().enumerate() {
+            })?
+        }
+        Command::Missing => {
+            let mod_list = data_manager.load_mod_file()?;
+            let rows = ModRow::from_mod_list(&mod_list, |m| !m.file_exists() && m.game == game);
+            output::print(format, &rows, |rows| {
+                for (i, n) in rows.iter().enumerate() {
                     println!("{i} - {}", n.name)
                 }
+            })?
+        }
+        Command::Save { name } => {
+            let mod_list = data_manager.load_mod_file()?;
+            // Overwriting a profile keeps its description, tags and notes
+            let mod_profile = if data_manager.list_profiles()?.contains(&name) {
+                data_manager
+                    .load_profile(name.clone())?
+                    .with_mods_from(&mod_list, &game)
+            } else {
+                ModProfile::new_from_mod_list(name.clone(), &mod_list, &game)
+            };
+            let mod_profile = data_manager.save_profile(mod_profile)?;
+            let rows = [ProfileRow::from(&mod_profile)];
+            output::print(format, &rows, |_| println!("Profile {name} saved."))?
+        }
+        Command::Edit {
+            name,
+            description,
+            author,
+            tag,
+            untag,
+            note,
+        } => {
+            let mut profile = data_manager.load_profile(name)?;
+            let non_empty = |s: String| Some(s).filter(|s| !s.is_empty());
+            if let Some(description) = description {
+                profile.description = non_empty(description);
             }
-            "save" => {
-                if let Some(name) = arg_profile {
-                    let mod_profile = ModProfile::new_from_mod_list(name.to_owned(), &mod_list);
-                    data_manager.save_profile(mod_profile)?;
-                    println!("Profile {name} saved.")
-                } else {
-                    println!("Missing profile name")
+            if let Some(author) = author {
+                profile.author = non_empty(author);
+            }
+            profile.tags.retain(|t| !untag.contains(t));
+            for t in tag {
+                if !profile.tags.contains(&t) {
+                    profile.tags.push(t);
+                }
+            }
+            for n in note {
+                let (query, text) = n
+                    .split_once('=')
+                    .ok_or_else(|| anyhow!("Notes look like MOD=NOTE, got \"{n}\""))?;
+                let i = profile
+                    .find_mod(query)
+                    .ok_or_else(|| anyhow!("No mod \"{query}\" in profile {}", profile.name))?;
+                profile.active_mods[i].note = non_empty(text.to_string());
+            }
+            let profile = data_manager.save_profile(profile)?;
+            let rows = [ProfileRow::from(&profile)];
+            output::print(format, &rows, |_| {
+                println!("Profile {} saved.", profile.name)
+            })?
+        }
+        Command::List => {
+            let rows = data_manager
+                .list_profiles()?
+                .into_iter()
+                .map(|name| Ok(ProfileRow::from(&data_manager.load_profile(name)?)))
+                .collect::<Result<Vec<_>>>()?;
+            output::print(format, &rows, |rows| {
+                for item in rows {
+                    println!("{}", item.name)
+                }
+            })?
+        }
+        Command::Show { name } => {
+            let profile = data_manager.load_profile(profile_or_preferred(name)?)?;
+            let mod_list = data_manager.load_mod_file().ok();
+            let rows = ProfileModRow::from_profile(&profile, mod_list.as_ref(), loosest);
+            output::print(format, &rows, |rows| {
+                println!("Profile \"{}\"", profile.name);
+                if let Some(description) = &profile.description {
+                    println!("{description}");
                 }
+                let local =
+                    |t: DateTime<Utc>| t.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string();
+                let details = [
+                    ("Game", profile.game.clone()),
+                    ("Author", profile.author.clone()),
+                    (
+                        "Tags",
+                        Some(profile.tags.join(", ")).filter(|t| !t.is_empty()),
+                    ),
+                    ("Created", profile.created.map(local)),
+                    ("Updated", profile.updated.map(local)),
+                ];
+                for (label, value) in details {
+                    if let Some(value) = value {
+                        println!("{label}: {value}");
+                    }
+                }
+                for (i, n) in rows.iter().enumerate() {
+                    match (&n.name, n.match_rule) {
+                        (Some(name), Some(MatchRule::Uuid)) => print!("{i} - {name}"),
+                        (Some(name), Some(rule)) => print!("{i} - {name} (by {rule})"),
+                        _ => print!("{i} - {} (not installed)", profile.active_mods[i]),
+                    }
+                    match &n.note {
+                        Some(note) => println!(" - {note}"),
+                        None => println!(),
+                    }
+                }
+            })?
+        }
+        Command::Share { name } => {
+            let profile = data_manager.load_profile(profile_or_preferred(name)?)?;
+            let rows = [ShareRow {
+                name: profile.name.clone(),
+                mods: profile.active_mods.len(),
+                code: profile.to_share_code()?,
+            }];
+            output::print(format, &rows, |rows| println!("{}", rows[0].code))?
+        }
+        Command::ImportCode { code, name, force } => {
+            let code = match code {
+                Some(code) => code,
+                None => io::read_to_string(io::stdin())?,
+            };
+            let mut profile = ModProfile::from_share_code(&code)?;
+            if let Some(name) = name {
+                profile.name = name;
+            }
+            if !force && data_manager.list_profiles()?.contains(&profile.name) {
+                bail!(
+                    "There's already a profile called {}; pick another with --name, or overwrite it with --force",
+                    profile.name
+                );
             }
-            "list" => {
-                let profiles = data_manager.list_profiles()?;
-                for item in profiles {
-                    println!("{item}")
+            let profile = data_manager.save_profile(profile)?;
+            let rows = [ProfileRow::from(&profile)];
+            output::print(format, &rows, |_| {
+                println!("Profile {} saved.", profile.name)
+            })?
+        }
+        Command::ImportFile { path, name, yes } => {
+            let theirs = DataManager::read_mod_file(&path)?;
+            let profile = ModProfile::new_from_mod_list(name.clone(), &theirs, &game);
+            let mod_list = data_manager.load_mod_file().ok();
+            let rows = ProfileModRow::from_profile(&profile, mod_list.as_ref(), loosest);
+            output::print(format, &rows, |rows| {
+                for n in rows {
+                    // Missing mods only have the name the friend's launcher gave them
+                    let name = theirs
+                        .mods()
+                        .into_iter()
+                        .find(|m| m.uuid.0 == n.uuid)
+                        .map_or(&n.uuid, |m| &m.name);
+                    let missing = if n.installed { "" } else { " (not installed)" };
+                    println!("{} - {name}{missing}", n.position)
                 }
+                let missing = rows.iter().filter(|r| !r.installed).count();
+                println!("{missing} of {} mods not installed here.", rows.len());
+            })?;
+
+            let question = if data_manager.list_profiles()?.contains(&name) {
+                format!("Overwrite profile {name}?")
+            } else {
+                format!("Save as profile {name}?")
+            };
+            if yes || confirm(&question)? {
+                data_manager.save_profile(profile)?;
+                eprintln!("Profile {name} saved.");
+            } else {
+                eprintln!("Not saved.");
             }
-            "show" => {
-                if let Some(name) = arg_profile {
-                    let profile = data_manager.load_profile(name.to_owned())?;
-                    println!("Profile \"{}\"", profile.name);
-                    for (i, n) in profile.active_mods.iter().enumerate() {
-                        println!("{i} - {}", n.0)
+        }
+        Command::Apply { name, dry_run } => {
+            let name = profile_or_preferred(name)?;
+            let profile = data_manager.load_profile(name.clone())?;
+            let before = data_manager.load_mod_file()?;
+            let (diff, report, after) = ModListDiff::for_profile(&before, &profile, loosest);
+            let changes = DiffRow::from_diff(&diff, &report, &before, &after);
+            let print_changes = |rows: &[DiffRow]| {
+                if rows.is_empty() {
+                    println!("Nothing to change.")
+                }
+                for r in rows {
+                    let name = r.name.as_ref().or(r.packfile.as_ref()).unwrap_or(&r.uuid);
+                    match (r.change, r.previous_position, r.position) {
+                        ("move", Some(from), Some(to)) => println!("move {name} {from} -> {to}"),
+                        ("not_installed", ..) => println!("not installed {name}"),
+                        ("unordered", _, Some(to)) => {
+                            println!("could not order {name}, put it at {to}")
+                        }
+                        (change, ..) => println!("{change} {name}"),
                     }
-                } else {
-                    println!("Missing profile name")
                 }
+                for m in report.matched.iter().filter(|m| m.rule != MatchRule::Uuid) {
+                    println!("found {} by {}", m.wanted, m.rule)
+                }
+            };
+
+            if dry_run {
+                output::print(format, &changes, print_changes)?
+            } else {
+                let origin = SnapshotOrigin::Profile { name };
+                data_manager.save_to_mod_file(after, origin)?;
+                // Scripts get the history entry, like `undo` and `restore`
+                output::print(format, &last_snapshot(&data_manager)?, |_| {
+                    print_changes(&changes)
+                })?
+            }
+        }
+        Command::Enable { mods } => set_active(&data_manager, format, &mods, &game, |_| true)?,
+        Command::Disable { mods } => set_active(&data_manager, format, &mods, &game, |_| false)?,
+        Command::Toggle { mods } => set_active(&data_manager, format, &mods, &game, |a| !a)?,
+        Command::Move { name, to } => {
+            let mut mod_list = data_manager.load_mod_file()?;
+            let from = mod_list.find_mod(&name, &game)?;
+            let uuid = mod_list.mods()[from].uuid.clone();
+            let uuid_of = |query: &str| -> Result<ModUUID> {
+                Ok(mod_list.mods()[mod_list.find_mod(query, &game)?]
+                    .uuid
+                    .clone())
+            };
+            if let Some(before) = &to.before {
+                let other = uuid_of(before)?;
+                mod_list.move_before(&uuid, &other)?
+            } else if let Some(after) = &to.after {
+                let other = uuid_of(after)?;
+                mod_list.move_after(&uuid, &other)?
+            } else if to.bottom {
+                mod_list.move_mod(from, mod_list.mods().len() - 1)?
+            } else {
+                mod_list.move_mod(from, 0)?
             }
-            "apply" => {
-                if let Some(name) = arg_profile {
-                    let profile = data_manager.load_profile(name.to_owned())?;
-                    let mut mod_list = mod_list;
-                    mod_list.apply_profile(profile);
 
-                    data_manager.save_to_mod_file(mod_list)?;
+            let target = mod_list.index_of(&uuid)?;
+            let workshop = read_workshop_items(&mod_list);
+            let rows = [ModRow::new(target + 1, mod_list.mods()[target], &workshop)];
+            data_manager.save_to_mod_file(mod_list, SnapshotOrigin::Edit)?;
+            output::print(format, &rows, |rows| {
+                println!("Moved {} to position {}", rows[0].name, rows[0].position)
+            })?
+        }
+        Command::Conflicts => {
+            let mod_list = data_manager.load_mod_file()?;
+            let report = ConflictReport::new(&mod_list, &game);
+            for (m, reason) in &report.unreadable {
+                eprintln!("Could not check {}: {reason}", m.name);
+            }
+            let rows = ConflictRow::from_report(&report, &mod_list);
+            output::print(format, &rows, |rows| {
+                if rows.is_empty() {
+                    println!("No conflicts.")
+                }
+                for r in rows {
+                    if r.wins {
+                        println!("{}", r.path);
+                    }
+                    let verb = if r.wins { "wins" } else { "loses" };
+                    println!("  {verb}: {} ({})", r.name, r.position);
+                }
+            })?
+        }
+        Command::Prune { dry_run } => {
+            let before = data_manager.load_mod_file()?;
+            let report = data_manager.prune_mod_file(dry_run)?;
+            let rows = ModRow::from_mod_list(&before, |m| report.removed.contains(m));
+            output::print(format, &rows, |rows| {
+                if rows.is_empty() {
+                    println!("No missing mods.")
+                } else if dry_run {
+                    println!("Would remove:")
                 } else {
-                    println!("Missing profile name")
+                    println!("Removed:")
+                }
+                for (i, n) in rows.iter().enumerate() {
+                    println!("{i} - {} ({})", n.name, n.packfile)
                 }
+                if let Some(backup) = &report.backup {
+                    println!("Backed up the old mod file to {}", backup.display())
+                }
+            })?
+        }
+        Command::Locate => {
+            let report = data_manager.discover_mod_files();
+            let found = report.found();
+            let rows: Vec<_> = report
+                .candidates
+                .iter()
+                .map(|c| CandidateRow::new(c, Some(c) == found))
+                .collect();
+            output::print(format, &rows, |_| print!("{report}"))?
+        }
+        Command::Files => {
+            let chosen = data_manager.settings().mod_file.clone();
+            let rows: Vec<_> = data_manager
+                .list_mod_files()
+                .iter()
+                .enumerate()
+                .map(|(i, f)| ModFileRow::new(i, f, f.path.canonicalize().ok() == chosen))
+                .collect();
+            output::print(format, &rows, |rows| {
+                if let Some(path) = &chosen {
+                    println!("Using {}", path.display());
+                }
+                for f in rows {
+                    let marker = if f.chosen { "*" } else { " " };
+                    let modified = f
+                        .modified
+                        .map(|t| t.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string())
+                        .unwrap_or_else(|| "?".to_string());
+                    let mod_count = f
+                        .mods
+                        .map(|c| format!("{c} mods"))
+                        .unwrap_or_else(|| "unreadable".to_string());
+                    println!(
+                        "{marker}{} - {} ({}, {mod_count}, modified {modified})",
+                        f.number,
+                        f.path.display(),
+                        f.source
+                    )
+                }
+            })?
+        }
+        Command::Choose { choice } => {
+            if choice == "auto" {
+                data_manager.choose_mod_file(None)?;
+            } else {
+                let path = match choice.parse::<usize>() {
+                    Ok(i) => data_manager
+                        .list_mod_files()
+                        .get(i)
+                        .map(|f| f.path.clone())
+                        .ok_or_else(|| anyhow!("No mod file number {i}"))?,
+                    Err(_) => PathBuf::from(choice),
+                };
+                data_manager.choose_mod_file(Some(path))?;
             }
-            other => println!("Unkown command: {other}"),
+            let rows = [ChoiceRow {
+                mod_file: data_manager.settings().mod_file.clone(),
+            }];
+            output::print(format, &rows, |rows| match &rows[0].mod_file {
+                Some(path) => println!("Using {} from now on.", path.display()),
+                None => println!("Mod file will be auto-detected."),
+            })?
+        }
+        Command::History => {
+            let rows: Vec<_> = data_manager
+                .history()?
+                .iter()
+                .map(SnapshotRow::from)
+                .collect();
+            for (path, reason) in data_manager.unreadable_history()? {
+                eprintln!("Skipped unreadable snapshot {}: {reason}", path.display());
+            }
+            output::print(format, &rows, |rows| {
+                for snapshot in rows {
+                    println!(
+                        "{} - {} - {} ({} active)",
+                        snapshot.id,
+                        snapshot
+                            .created
+                            .with_timezone(&Local)
+                            .format("%Y-%m-%d %H:%M"),
+                        snapshot.description,
+                        snapshot.active
+                    )
+                }
+            })?
+        }
+        Command::Undo => {
+            let snapshot = data_manager.undo()?;
+            output::print(format, &last_snapshot(&data_manager)?, |_| {
+                println!("Back to #{} ({})", snapshot.id, snapshot.origin)
+            })?
+        }
+        Command::Restore { id } => {
+            data_manager.restore_snapshot(id)?;
+            output::print(format, &last_snapshot(&data_manager)?, |_| {
+                println!("Restored #{id}")
+            })?
+        }
+        Command::Completions { shell } => {
+            let bin = Cli::command().get_name().to_string();
+            let completer = env::current_exe()
+                .map(|p| p.display().to_string())
+                .unwrap_or_else(|_| bin.clone());
+            Shells::builtins()
+                .completer(&shell)
+                .context("Unknown shell")?
+                .write_registration("COMPLETE", &bin, &bin, &completer, &mut io::stdout())?;
         }
-    } else {
-        println!("Missing command")
     }
     Ok(())
 }
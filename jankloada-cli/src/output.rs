@@ -0,0 +1,473 @@
+//! Rows printed by `--output json` and `--output csv`.
+//!
+//! These are the stable schema scripts can rely on: fields are only ever
+//! added, at the end. JSON is always an array of rows, CSV has a header
+//! line unless there are no rows at all.
+
+use std::{cmp::Reverse, collections::HashMap, io, path::PathBuf};
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use jankloada_lib::{
+    conflicts::ConflictReport,
+    diff::ModListDiff,
+    discovery::{Candidate, ModFileInfo},
+    history::Snapshot,
+    mod_data::{ApplyReport, MatchRule, ModEntry, ModList, ModProfile, ProfileMod},
+    steam::{read_workshop_items, WorkshopItem},
+};
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Made for humans, may change between versions
+    Table,
+    Json,
+    Csv,
+}
+
+/// Prints `rows` as JSON or CSV, or calls `table` to print them for humans.
+pub fn print<T: Serialize>(
+    format: OutputFormat,
+    rows: &[T],
+    table: impl FnOnce(&[T]),
+) -> Result<()> {
+    match format {
+        OutputFormat::Table => table(rows),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(rows)?),
+        OutputFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(io::stdout());
+            for row in rows {
+                writer.serialize(row)?;
+            }
+            writer.flush()?;
+        }
+    }
+    Ok(())
+}
+
+/// A mod in the launcher's mod file.
+#[derive(Serialize, Debug)]
+pub struct ModRow {
+    /// 1-based place in the load order of the whole mod file.
+    pub position: usize,
+    pub uuid: String,
+    pub name: String,
+    pub game: String,
+    pub packfile: String,
+    pub active: bool,
+    /// The pack file is gone.
+    pub missing: bool,
+    /// Empty if it's not from the Steam workshop.
+    pub workshop_id: Option<String>,
+    /// Bytes on disk, according to Steam.
+    pub workshop_size: Option<u64>,
+    /// When the installed version was published on the workshop.
+    pub updated: Option<DateTime<Utc>>,
+}
+
+impl ModRow {
+    pub fn new(
+        position: usize,
+        entry: &ModEntry,
+        workshop: &HashMap<String, WorkshopItem>,
+    ) -> Self {
+        let item = entry.workshop_id().and_then(|id| workshop.get(id));
+        Self {
+            position,
+            uuid: entry.uuid.0.clone(),
+            name: entry.name.clone(),
+            game: entry.game.clone(),
+            packfile: entry.packfile.clone(),
+            active: entry.active,
+            missing: !entry.file_exists(),
+            workshop_id: entry.workshop_id().map(str::to_string),
+            workshop_size: item.and_then(|i| i.size),
+            updated: item.and_then(|i| i.updated),
+        }
+    }
+
+    /// Every mod in `mod_list` that `filter` keeps, with its position in the full list.
+    pub fn from_mod_list(mod_list: &ModList, filter: impl Fn(&ModEntry) -> bool) -> Vec<Self> {
+        let workshop = read_workshop_items(mod_list);
+        mod_list
+            .mods()
+            .into_iter()
+            .enumerate()
+            .filter(|(_, m)| filter(m))
+            .map(|(i, m)| Self::new(i + 1, m, &workshop))
+            .collect()
+    }
+}
+
+/// What to sort listed mods by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ModOrder {
+    /// Load order
+    Position,
+    Name,
+    WorkshopId,
+    /// Most recently updated on the workshop first
+    Updated,
+}
+
+impl ModOrder {
+    /// Sorts `rows`; mods without the field go last, in load order.
+    pub fn sort(self, rows: &mut [ModRow]) {
+        match self {
+            ModOrder::Position => rows.sort_by_key(|r| r.position),
+            ModOrder::Name => rows.sort_by_cached_key(|r| r.name.to_lowercase()),
+            ModOrder::WorkshopId => rows.sort_by_key(|r| {
+                let id = r.workshop_id.as_ref().and_then(|id| id.parse::<u64>().ok());
+                (id.is_none(), id)
+            }),
+            ModOrder::Updated => rows.sort_by_key(|r| (r.updated.is_none(), Reverse(r.updated))),
+        }
+    }
+}
+
+/// A mod in a profile, with details from the mod file if it is in there.
+#[derive(Serialize, Debug)]
+pub struct ProfileModRow {
+    /// 1-based place in the profile's load order.
+    pub position: usize,
+    pub uuid: String,
+    pub name: Option<String>,
+    pub packfile: Option<String>,
+    /// Whether it is active in the mod file right now.
+    pub active: Option<bool>,
+    /// The mod is in the mod file.
+    pub installed: bool,
+    /// How it was found in the mod file: uuid, workshop_id or packfile.
+    pub match_rule: Option<MatchRule>,
+    /// The profile's note on this mod.
+    pub note: Option<String>,
+}
+
+impl ProfileModRow {
+    pub fn new(
+        position: usize,
+        wanted: &ProfileMod,
+        found: Option<(&ModEntry, MatchRule)>,
+    ) -> Self {
+        let entry = found.map(|(m, _)| m);
+        Self {
+            position,
+            uuid: wanted.uuid.0.clone(),
+            name: entry.map(|m| m.name.clone()),
+            packfile: entry.map(|m| m.packfile.clone()),
+            active: entry.map(|m| m.active),
+            installed: entry.is_some(),
+            match_rule: found.map(|(_, rule)| rule),
+            note: wanted.note.clone(),
+        }
+    }
+
+    /// Every mod in `profile`, looked up in `mod_list` if there is one.
+    pub fn from_profile(
+        profile: &ModProfile,
+        mod_list: Option<&ModList>,
+        loosest: MatchRule,
+    ) -> Vec<Self> {
+        let found = match mod_list {
+            Some(l) => l.match_profile(&profile.active_mods, profile.game.as_deref(), loosest),
+            None => vec![None; profile.active_mods.len()],
+        };
+        profile
+            .active_mods
+            .iter()
+            .zip(found)
+            .enumerate()
+            .map(|(i, (wanted, found))| {
+                let found = found
+                    .zip(mod_list)
+                    .map(|((j, rule), l)| (l.mods()[j], rule));
+                Self::new(i + 1, wanted, found)
+            })
+            .collect()
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct ProfileRow {
+    pub name: String,
+    /// Number of mods in the profile.
+    pub mods: usize,
+    pub description: Option<String>,
+    /// The launcher's game id, e.g. warhammer3.
+    pub game: Option<String>,
+    pub author: Option<String>,
+    /// Comma separated.
+    pub tags: String,
+    pub created: Option<DateTime<Utc>>,
+    pub updated: Option<DateTime<Utc>>,
+}
+
+impl From<&ModProfile> for ProfileRow {
+    fn from(profile: &ModProfile) -> Self {
+        Self {
+            name: profile.name.clone(),
+            mods: profile.active_mods.len(),
+            description: profile.description.clone(),
+            game: profile.game.clone(),
+            author: profile.author.clone(),
+            tags: profile.tags.join(","),
+            created: profile.created,
+            updated: profile.updated,
+        }
+    }
+}
+
+/// A profile packed into a share code.
+#[derive(Serialize, Debug)]
+pub struct ShareRow {
+    pub name: String,
+    /// Number of mods in the profile.
+    pub mods: usize,
+    pub code: String,
+}
+
+/// An entry in the history of written mod lists.
+#[derive(Serialize, Debug)]
+pub struct SnapshotRow {
+    pub id: u64,
+    pub created: DateTime<Utc>,
+    /// One of profile, edit, prune, external, restore or undo.
+    pub origin: String,
+    /// The origin spelled out, e.g. which profile was applied.
+    pub description: String,
+    /// Number of active mods.
+    pub active: usize,
+}
+
+impl From<&Snapshot> for SnapshotRow {
+    fn from(snapshot: &Snapshot) -> Self {
+        Self {
+            id: snapshot.id,
+            created: snapshot.created,
+            origin: snapshot.origin.kind().to_string(),
+            description: snapshot.origin.to_string(),
+            active: snapshot.mod_list.get_active().len(),
+        }
+    }
+}
+
+/// A place that was checked for the mod file.
+#[derive(Serialize, Debug)]
+pub struct CandidateRow {
+    pub source: String,
+    pub path: PathBuf,
+    /// This is the mod file that gets used.
+    pub found: bool,
+    /// Why it was not used, if it wasn't.
+    pub rejection: Option<String>,
+}
+
+impl CandidateRow {
+    pub fn new(candidate: &Candidate, found: bool) -> Self {
+        Self {
+            source: candidate.source.to_string(),
+            path: candidate.path.clone(),
+            found,
+            rejection: candidate.rejection.as_ref().map(|r| r.to_string()),
+        }
+    }
+}
+
+/// A mod file that was found.
+#[derive(Serialize, Debug)]
+pub struct ModFileRow {
+    /// What to pass to `choose`.
+    pub number: usize,
+    pub path: PathBuf,
+    pub source: String,
+    pub modified: Option<DateTime<Utc>>,
+    /// Number of mods in it, empty if it could not be read.
+    pub mods: Option<usize>,
+    /// Picked with `choose`.
+    pub chosen: bool,
+}
+
+impl ModFileRow {
+    pub fn new(number: usize, info: &ModFileInfo, chosen: bool) -> Self {
+        Self {
+            number,
+            path: info.path.clone(),
+            source: info.source.to_string(),
+            modified: info.modified.map(DateTime::from),
+            mods: info.mod_count,
+            chosen,
+        }
+    }
+}
+
+/// The mod file picked with `choose`.
+#[derive(Serialize, Debug)]
+pub struct ChoiceRow {
+    /// Empty when the mod file is auto-detected.
+    pub mod_file: Option<PathBuf>,
+}
+
+/// One mod shipping a file that other active mods ship too.
+#[derive(Serialize, Debug)]
+pub struct ConflictRow {
+    /// Path inside the pack.
+    pub path: String,
+    pub uuid: String,
+    pub name: String,
+    pub packfile: String,
+    /// 1-based place in the whole mod file.
+    pub position: usize,
+    /// The game uses this mod's copy.
+    pub wins: bool,
+}
+
+impl ConflictRow {
+    /// One row per mod per conflicting file, winner first.
+    pub fn from_report(report: &ConflictReport, mod_list: &ModList) -> Vec<Self> {
+        report
+            .conflicts
+            .iter()
+            .flat_map(|c| {
+                c.mods.iter().enumerate().map(|(i, m)| Self {
+                    path: c.path.clone(),
+                    uuid: m.uuid.0.clone(),
+                    name: m.name.clone(),
+                    packfile: m.packfile.clone(),
+                    position: mod_list.index_of(&m.uuid).map_or(0, |i| i + 1),
+                    wins: i == 0,
+                })
+            })
+            .collect()
+    }
+}
+
+/// One change `apply` makes.
+#[derive(Serialize, Debug)]
+pub struct DiffRow {
+    /// One of activate, deactivate, move, remove, add, not_installed or
+    /// unordered (in the profile, but could not be put in its order).
+    pub change: &'static str,
+    pub uuid: String,
+    pub name: Option<String>,
+    pub packfile: Option<String>,
+    /// 1-based place in the mod file afterwards, empty if it won't be in there.
+    pub position: Option<usize>,
+    /// 1-based place in the mod file now, empty if it isn't in there.
+    pub previous_position: Option<usize>,
+    /// For profile mods, how they were found in the mod file: uuid,
+    /// workshop_id or packfile.
+    pub match_rule: Option<MatchRule>,
+}
+
+impl DiffRow {
+    pub fn from_diff(
+        diff: &ModListDiff,
+        report: &ApplyReport,
+        before: &ModList,
+        after: &ModList,
+    ) -> Vec<Self> {
+        let row = |change, entry: &ModEntry| Self {
+            change,
+            uuid: entry.uuid.0.clone(),
+            name: Some(entry.name.clone()),
+            packfile: Some(entry.packfile.clone()),
+            position: after.index_of(&entry.uuid).ok().map(|i| i + 1),
+            previous_position: before.index_of(&entry.uuid).ok().map(|i| i + 1),
+            match_rule: report
+                .matched
+                .iter()
+                .find(|m| m.uuid == entry.uuid)
+                .map(|m| m.rule),
+        };
+        diff.activated
+            .iter()
+            .map(|m| row("activate", m))
+            .chain(diff.deactivated.iter().map(|m| row("deactivate", m)))
+            .chain(diff.moved.iter().map(|m| row("move", &m.entry)))
+            .chain(diff.removed.iter().map(|m| row("remove", m)))
+            .chain(diff.added.iter().map(|m| row("add", m)))
+            .chain(report.unordered.iter().map(|m| {
+                Self {
+                    // A second copy of a mod; the first one is the ordered one
+                    position: after
+                        .mods()
+                        .iter()
+                        .rposition(|e| e.uuid == m.uuid)
+                        .map(|i| i + 1),
+                    ..row("unordered", m)
+                }
+            }))
+            .chain(diff.not_installed.iter().map(|wanted| Self {
+                change: "not_installed",
+                uuid: wanted.uuid.0.clone(),
+                name: None,
+                // All we know is the name the profile has for it
+                packfile: wanted.packfile.clone(),
+                position: None,
+                previous_position: None,
+                match_rule: None,
+            }))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use jankloada_lib::mod_data::{ModEntry, ModUUID};
+
+    use super::ModRow;
+
+    const MOD_ROW_FIELDS: [&str; 10] = [
+        "position",
+        "uuid",
+        "name",
+        "game",
+        "packfile",
+        "active",
+        "missing",
+        "workshop_id",
+        "workshop_size",
+        "updated",
+    ];
+
+    fn row() -> ModRow {
+        let entry = ModEntry {
+            uuid: ModUUID("urk".to_string()),
+            name: "Urk".to_string(),
+            active: true,
+            category: "mod".to_string(),
+            game: "warhammer3".to_string(),
+            owned: true,
+            packfile: "/no/such/urk.pack".to_string(),
+            short: "".to_string(),
+            launcher_fields: Default::default(),
+        };
+        ModRow::new(3, &entry, &HashMap::new())
+    }
+
+    #[test]
+    fn mod_row_json_keys_are_stable() {
+        let json = serde_json::to_value(vec![row()]).unwrap();
+        let keys: Vec<_> = json[0].as_object().unwrap().keys().cloned().collect();
+        assert_eq!(MOD_ROW_FIELDS.to_vec(), keys);
+        assert_eq!(3, json[0]["position"]);
+        assert_eq!(true, json[0]["missing"]);
+    }
+
+    #[test]
+    fn mod_row_csv_header_is_stable() {
+        let mut writer = csv::Writer::from_writer(vec![]);
+        writer.serialize(row()).unwrap();
+        let csv = String::from_utf8(writer.into_inner().unwrap()).unwrap();
+        let mut lines = csv.lines();
+        assert_eq!(Some(MOD_ROW_FIELDS.join(",").as_str()), lines.next());
+        assert_eq!(
+            Some("3,urk,Urk,warhammer3,/no/such/urk.pack,true,true,,,"),
+            lines.next()
+        );
+    }
+}
@@ -0,0 +1,175 @@
+//! Conflicts between mods: files that more than one active mod's pack ships.
+//!
+//! The game loads the packs in load order and uses the first copy of a file
+//! it finds, comparing paths without case, so the earliest mod wins. Mods
+//! whose pack can't be read are left out of the comparison and reported.
+
+use std::collections::HashMap;
+
+use crate::mod_data::{ModEntry, ModList, ModUUID};
+use crate::pack::PackIndex;
+
+/// A file that more than one active mod ships.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    /// As the winning mod spells it; the game doesn't care about case.
+    pub path: String,
+    /// The mods that have it, in load order.
+    pub mods: Vec<ModEntry>,
+}
+
+impl Conflict {
+    /// The mod whose copy the game uses: the first one in the load order.
+    pub fn winner(&self) -> &ModEntry {
+        &self.mods[0]
+    }
+}
+
+/// Files shipped by more than one active mod, for one game.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConflictReport {
+    /// Sorted by path, ignoring case.
+    pub conflicts: Vec<Conflict>,
+    /// Active mods whose pack couldn't be read, and why.
+    pub unreadable: Vec<(ModEntry, String)>,
+}
+
+impl ConflictReport {
+    /// Reads the pack of every active mod for `game` and compares them.
+    pub fn new(mod_list: &ModList, game: &str) -> Self {
+        let mut unreadable = Vec::new();
+        let packs: Vec<_> = mod_list
+            .get_active()
+            .into_iter()
+            .filter(|m| m.game == game)
+            .filter_map(|m| match PackIndex::from_path(m.packfile_path()) {
+                Ok(index) => Some((m, index)),
+                Err(e) => {
+                    unreadable.push((m.clone(), format!("{e:#}")));
+                    None
+                }
+            })
+            .collect();
+        Self {
+            conflicts: find_conflicts(packs.iter().map(|(m, index)| (*m, index))),
+            unreadable,
+        }
+    }
+
+    /// The conflicts `uuid` is part of.
+    pub fn for_mod<'a>(&'a self, uuid: &'a ModUUID) -> impl Iterator<Item = &'a Conflict> {
+        self.conflicts
+            .iter()
+            .filter(move |c| c.mods.iter().any(|m| &m.uuid == uuid))
+    }
+}
+
+/// Files that are in more than one of `packs`, which have to be in load order.
+pub fn find_conflicts<'a>(
+    packs: impl IntoIterator<Item = (&'a ModEntry, &'a PackIndex)>,
+) -> Vec<Conflict> {
+    let mut by_path: HashMap<String, Conflict> = HashMap::new();
+    for (entry, index) in packs {
+        for file in &index.files {
+            let conflict = by_path
+                .entry(file.path.to_lowercase())
+                .or_insert_with(|| Conflict {
+                    path: file.path.clone(),
+                    mods: vec![],
+                });
+            // A pack listing a file twice doesn't conflict with itself
+            if conflict.mods.last().map(|m| &m.uuid) != Some(&entry.uuid) {
+                conflict.mods.push(entry.clone());
+            }
+        }
+    }
+    let mut conflicts: Vec<_> = by_path.into_values().filter(|c| c.mods.len() > 1).collect();
+    conflicts.sort_by_cached_key(|c| c.path.to_lowercase());
+    conflicts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_conflicts, ConflictReport};
+    use crate::mod_data::{ModEntry, ModList, ModUUID};
+    use crate::pack::{tests::build_pack, PackIndex};
+
+    fn entry(uuid: &str, packfile: String) -> ModEntry {
+        ModEntry {
+            uuid: ModUUID(uuid.to_string()),
+            name: uuid.to_string(),
+            active: true,
+            category: "mod".to_string(),
+            game: "warhammer3".to_string(),
+            owned: true,
+            packfile,
+            short: "".to_string(),
+            launcher_fields: Default::default(),
+        }
+    }
+
+    fn pack(paths: &[&str]) -> PackIndex {
+        let files: Vec<_> = paths.iter().map(|p| (*p, &b"x"[..])).collect();
+        PackIndex::read(build_pack(0, &[], &files).as_slice()).unwrap()
+    }
+
+    #[test]
+    fn earlier_mods_win() {
+        let (urk, spanners, grots) = (
+            entry("urk", "urk.pack".to_string()),
+            entry("spanners", "spanners.pack".to_string()),
+            entry("grots", "grots.pack".to_string()),
+        );
+        let urk_pack = pack(&["db/units_tables/boyz", "script/waaagh.lua"]);
+        let spanners_pack = pack(&["DB/Units_Tables/Boyz", "db/spanners"]);
+        let grots_pack = pack(&["script/waaagh.lua", "db/spanners", "db/grots"]);
+
+        let conflicts = find_conflicts([
+            (&spanners, &spanners_pack),
+            (&urk, &urk_pack),
+            (&grots, &grots_pack),
+        ]);
+        let summary: Vec<_> = conflicts
+            .iter()
+            .map(|c| {
+                let names: Vec<_> = c.mods.iter().map(|m| m.name.as_str()).collect();
+                (c.path.as_str(), c.winner().name.as_str(), names)
+            })
+            .collect();
+        assert_eq!(
+            vec![
+                ("db/spanners", "spanners", vec!["spanners", "grots"]),
+                ("DB/Units_Tables/Boyz", "spanners", vec!["spanners", "urk"]),
+                ("script/waaagh.lua", "urk", vec!["urk", "grots"]),
+            ],
+            summary
+        );
+    }
+
+    #[test]
+    fn reads_packs_of_active_mods() {
+        let dir = tempfile::tempdir().unwrap();
+        let write = |name: &str, paths: &[&str]| {
+            let path = dir.path().join(name);
+            let files: Vec<_> = paths.iter().map(|p| (*p, &b"x"[..])).collect();
+            std::fs::write(&path, build_pack(0, &[], &files)).unwrap();
+            path.display().to_string()
+        };
+        let mut inactive = entry("inactive", write("inactive.pack", &["db/a"]));
+        inactive.active = false;
+        let mod_list = ModList(vec![
+            entry("one", write("one.pack", &["db/a", "db/b"])),
+            inactive,
+            entry("two", write("two.pack", &["db/a"])),
+            entry("gone", dir.path().join("gone.pack").display().to_string()),
+        ]);
+
+        let report = ConflictReport::new(&mod_list, "warhammer3");
+        assert_eq!(1, report.conflicts.len());
+        assert_eq!(2, report.conflicts[0].mods.len());
+        assert_eq!(1, report.for_mod(&ModUUID("two".to_string())).count());
+        assert_eq!(0, report.for_mod(&ModUUID("inactive".to_string())).count());
+        assert_eq!(1, report.unreadable.len());
+        assert_eq!("gone", report.unreadable[0].0.name);
+    }
+}
@@ -1,128 +1,431 @@
-use std::{fs, path::PathBuf};
+use std::{
+    env, fs,
+    io::Write,
+    path::{Path, PathBuf},
+};
 
-use anyhow::{anyhow, Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::Utc;
 use directories::BaseDirs;
 
-use crate::mod_data::{ModFileDTO, ModList, ModProfile};
+use crate::discovery::{DiscoveryReport, ModFileInfo, ModFileSource};
+use crate::history::{undo_target, Snapshot, SnapshotOrigin};
+use crate::mod_data::{ModFileDTO, ModList, ModProfile, PruneReport};
+use crate::settings::{Settings, SETTINGS_FILE};
+#[cfg(target_os = "linux")]
+use crate::{discovery::linux_steam_roots, launchers::wine_prefixes};
 
-const CA_MOD_FILE: &str = "20190104-moddata.dat";
+pub(crate) const CA_MOD_FILE: &str = "20190104-moddata.dat";
+/// Relative to a Windows (or Wine) user profile.
+pub(crate) const CA_LAUNCHER_DIR: &str = "AppData/Roaming/The Creative Assembly/Launcher";
+
+/// Overrides the mod file, like `DataManagerBuilder::mod_file`.
+pub const MOD_FILE_ENV: &str = "JANKLOADA_MOD_FILE";
+/// Overrides the data dir, like `DataManagerBuilder::data_dir`.
+pub const DATA_DIR_ENV: &str = "JANKLOADA_DATA_DIR";
+
+/// Files that were skipped, and why.
+type UnreadableFiles = Vec<(PathBuf, String)>;
 
 #[derive(Debug)]
 pub struct DataManager {
     base_dirs: BaseDirs,
     data_dir: PathBuf,
+    settings_path: PathBuf,
     custom_mod_file_path: Option<PathBuf>,
+    settings: Settings,
 }
 
-impl DataManager {
-    pub fn new() -> Result<Self> {
+/// Sets up a `DataManager` with paths other than the defaults.
+#[derive(Debug, Default)]
+pub struct DataManagerBuilder {
+    data_dir: Option<PathBuf>,
+    mod_file: Option<PathBuf>,
+}
+
+impl DataManagerBuilder {
+    /// Reads settings from here instead of the user data dir, and keeps profiles
+    /// here too unless the settings point somewhere else.
+    pub fn data_dir(mut self, path: impl Into<PathBuf>) -> Self {
+        self.data_dir = Some(path.into());
+        self
+    }
+
+    /// Uses this mod file, no matter what the settings say or what is discovered.
+    pub fn mod_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.mod_file = Some(path.into());
+        self
+    }
+
+    /// Fills in whatever was not set explicitly from the `JANKLOADA_*` env vars.
+    pub fn env(mut self) -> Self {
+        let var = |k| env::var_os(k).filter(|v| !v.is_empty()).map(PathBuf::from);
+        self.data_dir = self.data_dir.or_else(|| var(DATA_DIR_ENV));
+        self.mod_file = self.mod_file.or_else(|| var(MOD_FILE_ENV));
+        self
+    }
+
+    pub fn build(self) -> Result<DataManager> {
         let base_dirs = BaseDirs::new().context("Could not get user base dirs")?;
-        let data_dir = base_dirs.data_dir().join("jankloada");
-        fs::create_dir_all(base_dirs.data_dir().join("jankloada"))
-            .context("Could not create app data dir")?;
-        Ok(Self {
+        let settings_dir = self
+            .data_dir
+            .clone()
+            .unwrap_or_else(|| base_dirs.data_dir().join("jankloada"));
+        fs::create_dir_all(&settings_dir).context("Could not create app data dir")?;
+        let settings_path = settings_dir.join(SETTINGS_FILE);
+        let settings = load_settings(&settings_path)?;
+
+        let data_dir = match &settings.data_dir {
+            Some(dir) => {
+                fs::create_dir_all(dir).context(format!("Could not create data dir {dir:?}"))?;
+                dir.clone()
+            }
+            None => settings_dir,
+        };
+        Ok(DataManager {
             base_dirs,
             data_dir,
-            custom_mod_file_path: None,
+            settings_path,
+            custom_mod_file_path: self.mod_file,
+            settings,
         })
     }
+}
 
+impl DataManager {
+    /// Uses the default paths, unless overridden by env vars or the settings file.
+    pub fn new() -> Result<Self> {
+        Self::builder().env().build()
+    }
+
+    pub fn builder() -> DataManagerBuilder {
+        DataManagerBuilder::default()
+    }
+
+    pub fn settings(&self) -> &Settings {
+        &self.settings
+    }
+
+    /// Replaces and saves the settings. A new `data_dir` takes effect on the next start.
+    pub fn set_settings(&mut self, settings: Settings) -> Result<()> {
+        self.settings = settings;
+        let contents = toml::to_string_pretty(&self.settings)?;
+        write_atomic(&self.settings_path, contents).context("Failed to write settings")?;
+        Ok(())
+    }
+
+    /// Checks every location the mod file could be in on this platform.
     #[cfg(target_os = "linux")]
-    fn resolve_mod_file_path_platform(&self) -> Result<PathBuf> {
-        // magic twwh3 steam id: 1142710
-        let steam_proton_append = "steamapps/compatdata/1142710/pfx/drive_c/users/steamuser/AppData/Roaming/The Creative Assembly/Launcher/";
-        let paths = vec![
-            PathBuf::from(CA_MOD_FILE),
-            self.base_dirs
-                .home_dir()
-                .join(".steam/steam/")
-                .join(steam_proton_append)
-                .join(CA_MOD_FILE),
-            self.base_dirs
-                .home_dir()
-                .join("Games/SteamLibrary/Default/")
-                .join(steam_proton_append)
-                .join(CA_MOD_FILE),
-        ];
-        paths
-            .into_iter()
-            .find(|p| p.exists())
-            .ok_or_else(|| anyhow!("Could not find mod file!"))
+    pub fn discover_mod_files(&self) -> DiscoveryReport {
+        let mut report = DiscoveryReport::default();
+        report.check_file(ModFileSource::WorkingDir, PathBuf::from(CA_MOD_FILE));
+        for (kind, root) in linux_steam_roots(self.base_dirs.home_dir()) {
+            report.check_steam_root(kind, root);
+        }
+        for prefix in wine_prefixes(self.base_dirs.home_dir()) {
+            report.check_wine_prefix(prefix);
+        }
+        report
     }
 
+    /// Checks every location the mod file could be in on this platform.
     #[cfg(target_os = "windows")]
-    fn resolve_mod_file_path_platform(&self) -> Result<PathBuf> {
-        let paths = vec![
-            PathBuf::from(CA_MOD_FILE),
+    pub fn discover_mod_files(&self) -> DiscoveryReport {
+        let mut report = DiscoveryReport::default();
+        report.check_file(ModFileSource::WorkingDir, PathBuf::from(CA_MOD_FILE));
+        report.check_file(
+            ModFileSource::AppData,
             self.base_dirs
                 .data_dir()
                 .join("The Creative Assembly")
                 .join("Launcher")
                 .join(CA_MOD_FILE),
-        ];
-        paths
-            .into_iter()
-            .find(|p| p.exists())
-            .ok_or_else(|| anyhow!("Could not find mod file!"))
+        );
+        report
     }
 
+    /// Checks every location the mod file could be in on this platform.
     #[cfg(target_os = "macos")]
+    pub fn discover_mod_files(&self) -> DiscoveryReport {
+        let mut report = DiscoveryReport::default();
+        report.check_file(ModFileSource::WorkingDir, PathBuf::from(CA_MOD_FILE));
+        report
+    }
+
     fn resolve_mod_file_path_platform(&self) -> Result<PathBuf> {
-        let paths = vec![PathBuf::from(CA_MOD_FILE)];
-        paths
-            .into_iter()
-            .find(|p| p.exists())
-            .ok_or_else(|| anyhow!("Could not find mod file!"))
+        let report = self.discover_mod_files();
+        report
+            .found()
+            .map(|c| c.path.clone())
+            .ok_or_else(|| anyhow!("Could not find mod file! Looked in:\n{report}"))
+    }
+
+    /// Every mod file that was found, with details to tell them apart.
+    pub fn list_mod_files(&self) -> Vec<ModFileInfo> {
+        self.discover_mod_files().mod_files()
+    }
+
+    /// Pins the mod file to use from now on, or goes back to auto-detection with `None`.
+    pub fn choose_mod_file(&mut self, path: Option<PathBuf>) -> Result<()> {
+        let mod_file = path
+            .map(|p| p.canonicalize().context(format!("Could not find {p:?}")))
+            .transpose()?;
+        self.set_settings(Settings {
+            mod_file,
+            ..self.settings.clone()
+        })
     }
 
     pub fn resolve_mod_file_path(&self) -> Result<PathBuf> {
-        if let Some(c_path) = self.custom_mod_file_path.clone() {
-            Ok(c_path)
+        let custom = self
+            .custom_mod_file_path
+            .as_ref()
+            .or(self.settings.mod_file.as_ref());
+        if let Some(c_path) = custom {
+            Ok(c_path.clone())
         } else {
             self.resolve_mod_file_path_platform()
         }
     }
 
-    fn resolve_profile_path(&self, name: &String) -> PathBuf {
+    fn check_profile_name(name: &str) -> Result<()> {
+        // Names can come from share codes, so keep them inside the data dir
+        if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']) {
+            bail!("\"{name}\" can't be a profile name, it has to be a plain file name");
+        }
+        if Path::new(SETTINGS_FILE).file_stem() == Some(name.as_ref()) {
+            bail!("\"{name}\" is reserved for the settings file, pick another profile name");
+        }
+        Ok(())
+    }
+
+    /// Where profile `name` lives, if that's an allowed name.
+    fn resolve_profile_path(&self, name: &str) -> Result<PathBuf> {
+        Self::check_profile_name(name)?;
         let mut file_name = self.data_dir.join(name);
         file_name.set_extension("toml");
-        file_name
+        Ok(file_name)
     }
 
     pub fn load_mod_file(&self) -> Result<ModList> {
-        let mod_file_path = self.resolve_mod_file_path()?;
-        let data = fs::read_to_string(&mod_file_path)
-            .context(format!("Failed to load mod file: {mod_file_path:?}"))?;
+        Self::read_mod_file(&self.resolve_mod_file_path()?)
+    }
+
+    /// Reads any launcher mod file, e.g. one a friend sent over.
+    pub fn read_mod_file(path: &Path) -> Result<ModList> {
+        let data =
+            fs::read_to_string(path).context(format!("Failed to load mod file: {path:?}"))?;
         let parsed: ModList = serde_json::from_str::<ModFileDTO>(&data)
             .context("Could not parse mod file contents")?
             .into();
         Ok(parsed)
     }
 
-    pub fn save_to_mod_file(&self, mod_list: ModList) -> Result<()> {
-        let mod_file_dto: ModFileDTO = mod_list.into();
+    /// Backs up the current mod file, then replaces it with `mod_list` and
+    /// records it in the history as caused by `origin`. Returns the backup, if
+    /// one was made.
+    pub fn save_to_mod_file(
+        &self,
+        mod_list: ModList,
+        origin: SnapshotOrigin,
+    ) -> Result<Option<PathBuf>> {
+        let path = self.resolve_mod_file_path()?;
+        // Stick to whichever formatting the file already has
+        let pretty = fs::read_to_string(&path)
+            .map(|d| d.trim_end().contains('\n'))
+            .unwrap_or(false);
+        let mod_file_dto = ModFileDTO::from(mod_list.clone());
+        let contents = if pretty {
+            serde_json::to_string_pretty(&mod_file_dto)?
+        } else {
+            serde_json::to_string(&mod_file_dto)?
+        };
+
+        // Keep whatever the launcher did since our last write undoable too,
+        // as long as the history can be read
+        if let (Ok(on_disk), Ok(history)) = (self.load_mod_file(), self.history()) {
+            if history
+                .last()
+                .map(|s| s.mod_list != on_disk)
+                .unwrap_or(true)
+            {
+                self.record_snapshot(on_disk, SnapshotOrigin::External)?;
+            }
+        }
+
+        let backup = self.backup_mod_file()?;
+        write_atomic(&path, contents).context("Failed to write mod file")?;
+        self.record_snapshot(mod_list, origin)?;
+        Ok(backup)
+    }
+
+    /// Removes mods with missing pack files from the mod file. Unless `dry_run`
+    /// is set, the mod file is backed up and saved if anything was removed.
+    pub fn prune_mod_file(&self, dry_run: bool) -> Result<PruneReport> {
+        let mut mod_list = self.load_mod_file()?;
+        let mut report = mod_list.prune_missing();
+        if !dry_run && !report.removed.is_empty() {
+            report.backup = self.save_to_mod_file(mod_list, SnapshotOrigin::Prune)?;
+        }
+        Ok(report)
+    }
+
+    fn backup_dir(&self) -> PathBuf {
+        self.data_dir.join("backups")
+    }
+
+    /// Copies the mod file into the backup dir, dropping the oldest backups
+    /// beyond the configured count. Returns the new backup, if one was made.
+    pub fn backup_mod_file(&self) -> Result<Option<PathBuf>> {
         let path = self.resolve_mod_file_path()?;
-        let contents = serde_json::to_string_pretty(&mod_file_dto)?;
-        fs::write(path, contents).context("Failed to write mod file")?;
+        let keep = self.settings.backup_count();
+        if keep == 0 || !path.exists() {
+            return Ok(None);
+        }
+        fs::create_dir_all(self.backup_dir()).context("Could not create backup dir")?;
+        let timestamp = Utc::now().format("%Y%m%dT%H%M%S%.9fZ");
+        let backup = self.backup_dir().join(format!("{timestamp}-{CA_MOD_FILE}"));
+        fs::copy(&path, &backup).context(format!("Failed to back up mod file to {backup:?}"))?;
+
+        let backups = self.list_backups()?;
+        for old in backups.iter().skip(keep) {
+            fs::remove_file(old).context(format!("Failed to remove old backup {old:?}"))?;
+        }
+        Ok(Some(backup))
+    }
+
+    /// Mod file backups, newest first.
+    pub fn list_backups(&self) -> Result<Vec<PathBuf>> {
+        if !self.backup_dir().exists() {
+            return Ok(vec![]);
+        }
+        let mut backups = fs::read_dir(self.backup_dir())
+            .context("Failed to read backup dir")?
+            .filter_map(|i| i.ok())
+            .map(|f| f.path())
+            .filter(|p| p.to_string_lossy().ends_with(CA_MOD_FILE))
+            .collect::<Vec<_>>();
+        // Timestamps are zero-padded, so name order is age order
+        backups.sort();
+        backups.reverse();
+        Ok(backups)
+    }
+
+    fn history_dir(&self) -> PathBuf {
+        self.data_dir.join("history")
+    }
+
+    /// Every mod list we have written, oldest first. Snapshots that can't be
+    /// read are skipped, see `unreadable_history`.
+    pub fn history(&self) -> Result<Vec<Snapshot>> {
+        Ok(self.read_history()?.0)
+    }
+
+    /// Snapshot files in the history that can't be read, and why.
+    pub fn unreadable_history(&self) -> Result<Vec<(PathBuf, String)>> {
+        Ok(self.read_history()?.1)
+    }
+
+    fn read_history(&self) -> Result<(Vec<Snapshot>, UnreadableFiles)> {
+        if !self.history_dir().exists() {
+            return Ok((vec![], vec![]));
+        }
+        let mut history = vec![];
+        let mut unreadable = vec![];
+        let paths = fs::read_dir(self.history_dir())
+            .context("Failed to read history dir")?
+            .filter_map(|i| i.ok())
+            .map(|f| f.path())
+            .filter(|p| p.extension().map(|e| e == "json").unwrap_or(false));
+        for path in paths {
+            let snapshot = fs::read_to_string(&path)
+                .context("Could not read it")
+                .and_then(|data| Ok(serde_json::from_str::<Snapshot>(&data)?));
+            match snapshot {
+                Ok(snapshot) => history.push(snapshot),
+                Err(e) => unreadable.push((path, format!("{e:#}"))),
+            }
+        }
+        history.sort_by_key(|s| s.id);
+        unreadable.sort();
+        Ok((history, unreadable))
+    }
+
+    fn record_snapshot(&self, mod_list: ModList, origin: SnapshotOrigin) -> Result<Snapshot> {
+        fs::create_dir_all(self.history_dir()).context("Could not create history dir")?;
+        let (history, unreadable) = self.read_history()?;
+        // Don't reuse the number of a snapshot that can't be read
+        let last_id = history
+            .iter()
+            .map(|s| s.id)
+            .chain(
+                unreadable
+                    .iter()
+                    .filter_map(|(p, _)| p.file_stem()?.to_str()?.parse().ok()),
+            )
+            .max();
+        let snapshot = Snapshot {
+            id: last_id.map(|id| id + 1).unwrap_or(1),
+            created: Utc::now(),
+            origin,
+            mod_list,
+        };
+        let path = self.history_dir().join(format!("{}.json", snapshot.id));
+        write_atomic(&path, serde_json::to_string(&snapshot)?)
+            .context("Failed to write history")?;
+
+        let keep = self.settings.history_count();
+        for old in history.iter().rev().skip(keep.saturating_sub(1)) {
+            let path = self.history_dir().join(format!("{}.json", old.id));
+            fs::remove_file(&path).context(format!("Failed to remove old snapshot {path:?}"))?;
+        }
+        Ok(snapshot)
+    }
+
+    /// Writes the mod list from snapshot `id` back to the mod file.
+    pub fn restore_snapshot(&self, id: u64) -> Result<()> {
+        let snapshot = self
+            .history()?
+            .into_iter()
+            .find(|s| s.id == id)
+            .ok_or_else(|| anyhow!("No snapshot #{id} in history"))?;
+        self.save_to_mod_file(snapshot.mod_list, SnapshotOrigin::Restore { id })?;
         Ok(())
     }
 
-    pub fn save_profile(&self, mod_profile: ModProfile) -> Result<()> {
-        let path = self.resolve_profile_path(&mod_profile.name);
+    /// Goes back to the mod list before the last change, returning the snapshot used.
+    pub fn undo(&self) -> Result<Snapshot> {
+        let history = self.history()?;
+        let target = undo_target(&history)
+            .cloned()
+            .ok_or_else(|| anyhow!("Nothing to undo"))?;
+        let origin = SnapshotOrigin::Undo {
+            restored: target.id,
+        };
+        self.save_to_mod_file(target.mod_list.clone(), origin)?;
+        Ok(target)
+    }
+
+    /// Writes `mod_profile`, stamping when it was created and last updated, and
+    /// returns it as written.
+    pub fn save_profile(&self, mut mod_profile: ModProfile) -> Result<ModProfile> {
+        let path = self.resolve_profile_path(&mod_profile.name)?;
+        let now = Utc::now();
+        mod_profile.created.get_or_insert(now);
+        mod_profile.updated = Some(now);
         let contents = toml::to_string_pretty(&mod_profile)?;
-        fs::write(path, contents).context("Failed to write mod profile")?;
-        Ok(())
+        write_atomic(&path, contents).context("Failed to write mod profile")?;
+        Ok(mod_profile)
     }
 
     pub fn load_profile(&self, name: String) -> Result<ModProfile> {
-        let path = self.resolve_profile_path(&name);
+        let path = self.resolve_profile_path(&name)?;
         let data = fs::read_to_string(path).context("Could not read mod profile")?;
-        let parsed: ModProfile = toml::from_str(&data)?;
-        Ok(parsed)
+        ModProfile::from_toml(&data).with_context(|| format!("Could not read mod profile {name}"))
     }
 
     pub fn delete_profile(&self, name: String) -> Result<()> {
-        let path = self.resolve_profile_path(&name);
+        let path = self.resolve_profile_path(&name)?;
         fs::remove_file(path)?;
         Ok(())
     }
@@ -132,6 +435,7 @@ impl DataManager {
             .context("Failed to read data dir")?
             .filter_map(|i| i.ok())
             .filter(|p| p.path().extension().map(|e| e == "toml").unwrap_or(false))
+            .filter(|p| p.file_name() != SETTINGS_FILE)
             .map(|f| f.path())
             .collect::<Vec<_>>();
         let profiles = paths
@@ -142,3 +446,239 @@ impl DataManager {
         Ok(profiles)
     }
 }
+
+/// Writes to a temporary file next to `path` and renames it over `path`, so
+/// a crash mid-write leaves either the old or the new contents behind.
+fn write_atomic(path: &Path, contents: impl AsRef<[u8]>) -> Result<()> {
+    let file_name = path
+        .file_name()
+        .context(format!("Not a file path: {path:?}"))?
+        .to_string_lossy();
+    let tmp_path = path.with_file_name(format!(".{file_name}.jankloada-tmp"));
+    let mut tmp = fs::File::create(&tmp_path).context(format!("Could not create {tmp_path:?}"))?;
+    tmp.write_all(contents.as_ref())?;
+    tmp.sync_all()?;
+    drop(tmp);
+    fs::rename(&tmp_path, path).context(format!("Could not replace {path:?}"))?;
+    Ok(())
+}
+
+fn load_settings(path: &Path) -> Result<Settings> {
+    if !path.exists() {
+        return Ok(Settings::default());
+    }
+    let data = fs::read_to_string(path).context("Could not read settings")?;
+    toml::from_str(&data).context(format!("Could not parse settings: {path:?}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs,
+        path::{Path, PathBuf},
+    };
+
+    use super::DataManager;
+    use crate::{
+        history::SnapshotOrigin,
+        mod_data::{ModFileDTO, ModList, ModProfile},
+        settings::Settings,
+    };
+
+    fn dir_contents(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn settings_redirect_profiles_and_mod_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let profiles = dir.path().join("profiles");
+        let mut manager = DataManager::builder().data_dir(dir.path()).build().unwrap();
+        manager
+            .set_settings(Settings {
+                mod_file: Some(PathBuf::from("/from/settings.dat")),
+                data_dir: Some(profiles.clone()),
+                ..Default::default()
+            })
+            .unwrap();
+
+        // Settings are read back from the explicit data dir
+        let manager = DataManager::builder().data_dir(dir.path()).build().unwrap();
+        assert_eq!(
+            PathBuf::from("/from/settings.dat"),
+            manager.resolve_mod_file_path().unwrap()
+        );
+        manager
+            .save_profile(ModProfile::new("waaagh".to_string(), vec![]))
+            .unwrap();
+        assert!(profiles.join("waaagh.toml").exists());
+        assert_eq!(vec!["waaagh".to_string()], manager.list_profiles().unwrap());
+        fs::write(dir.path().join("escaped.toml"), "name = \"escaped\"").unwrap();
+        for bad in ["../escaped", "", "settings", ".hidden"] {
+            assert!(manager
+                .save_profile(ModProfile::new(bad.to_string(), vec![]))
+                .is_err());
+            assert!(manager.load_profile(bad.to_string()).is_err());
+            assert!(manager.delete_profile(bad.to_string()).is_err());
+        }
+        assert!(dir.path().join("escaped.toml").exists());
+
+        let manager = DataManager::builder()
+            .data_dir(dir.path())
+            .mod_file("/from/builder.dat")
+            .build()
+            .unwrap();
+        assert_eq!(
+            PathBuf::from("/from/builder.dat"),
+            manager.resolve_mod_file_path().unwrap()
+        );
+    }
+
+    #[test]
+    fn chosen_mod_file_is_kept_in_settings() {
+        let dir = tempfile::tempdir().unwrap();
+        let mod_file = dir.path().join("chosen.dat");
+        fs::write(&mod_file, "[]").unwrap();
+        let mut manager = DataManager::builder().data_dir(dir.path()).build().unwrap();
+
+        assert!(manager
+            .choose_mod_file(Some(dir.path().join("gone.dat")))
+            .is_err());
+        assert_eq!(None, manager.settings().mod_file);
+
+        manager.choose_mod_file(Some(mod_file.clone())).unwrap();
+        let mut manager = DataManager::builder().data_dir(dir.path()).build().unwrap();
+        assert_eq!(
+            mod_file.canonicalize().unwrap(),
+            manager.resolve_mod_file_path().unwrap()
+        );
+
+        manager.choose_mod_file(None).unwrap();
+        let manager = DataManager::builder().data_dir(dir.path()).build().unwrap();
+        assert_eq!(None, manager.settings().mod_file);
+    }
+
+    #[test]
+    fn saving_keeps_limited_backups() {
+        let dir = tempfile::tempdir().unwrap();
+        let mod_file = dir.path().join("20190104-moddata.dat");
+        let mut manager = DataManager::builder()
+            .data_dir(dir.path())
+            .mod_file(&mod_file)
+            .build()
+            .unwrap();
+        manager
+            .set_settings(Settings {
+                backup_count: Some(2),
+                ..Default::default()
+            })
+            .unwrap();
+
+        let original = r#"[{"uuid":"a","name":"A","active":true,"category":"mod","game":"warhammer3","order":1,"owned":true,"packfile":"Z:/a.pack","short":"a"}]"#;
+        fs::write(&mod_file, original).unwrap();
+        let mod_list = serde_json::from_str::<ModFileDTO>(original).unwrap().into();
+        manager
+            .save_to_mod_file(mod_list, SnapshotOrigin::Edit)
+            .unwrap();
+        assert_eq!(original, fs::read_to_string(&mod_file).unwrap());
+
+        let backups = manager.list_backups().unwrap();
+        assert_eq!(1, backups.len());
+        assert_eq!(original, fs::read_to_string(&backups[0]).unwrap());
+
+        for _ in 0..3 {
+            let mod_list = manager.load_mod_file().unwrap();
+            manager
+                .save_to_mod_file(mod_list, SnapshotOrigin::Edit)
+                .unwrap();
+        }
+        assert_eq!(2, manager.list_backups().unwrap().len());
+        // No temporary files left behind
+        assert_eq!(
+            vec![
+                "20190104-moddata.dat",
+                "backups",
+                "history",
+                "settings.toml"
+            ],
+            dir_contents(dir.path())
+        );
+    }
+
+    #[test]
+    fn undo_and_restore_go_through_history() {
+        let dir = tempfile::tempdir().unwrap();
+        let mod_file = dir.path().join("20190104-moddata.dat");
+        let manager = DataManager::builder()
+            .data_dir(dir.path())
+            .mod_file(&mod_file)
+            .build()
+            .unwrap();
+        let with_active = |active: bool| {
+            format!(
+                r#"[{{"uuid":"a","name":"A","active":{active},"category":"mod","game":"warhammer3","order":1,"owned":true,"packfile":"Z:/a.pack","short":"a"}}]"#
+            )
+        };
+        fs::write(&mod_file, with_active(true)).unwrap();
+
+        let off = serde_json::from_str::<ModFileDTO>(&with_active(false)).unwrap();
+        let profile = SnapshotOrigin::Profile {
+            name: "off".to_string(),
+        };
+        manager
+            .save_to_mod_file(off.into(), profile.clone())
+            .unwrap();
+
+        let history = manager.history().unwrap();
+        assert_eq!(
+            vec![SnapshotOrigin::External, profile],
+            history.iter().map(|s| s.origin.clone()).collect::<Vec<_>>()
+        );
+
+        assert_eq!(1, manager.undo().unwrap().id);
+        assert!(manager.load_mod_file().unwrap().mods()[0].active);
+        assert!(manager.undo().is_err());
+
+        manager.restore_snapshot(2).unwrap();
+        assert!(!manager.load_mod_file().unwrap().mods()[0].active);
+        assert_eq!(4, manager.history().unwrap().len());
+    }
+
+    #[test]
+    fn corrupt_snapshots_do_not_block_saving() {
+        let dir = tempfile::tempdir().unwrap();
+        let mod_file = dir.path().join("20190104-moddata.dat");
+        let manager = DataManager::builder()
+            .data_dir(dir.path())
+            .mod_file(&mod_file)
+            .build()
+            .unwrap();
+        let original = r#"[{"uuid":"a","name":"A","active":true,"category":"mod","game":"warhammer3","order":1,"owned":true,"packfile":"Z:/a.pack","short":"a"}]"#;
+        fs::write(&mod_file, original).unwrap();
+        let mod_list: ModList = serde_json::from_str::<ModFileDTO>(original).unwrap().into();
+        manager
+            .save_to_mod_file(mod_list.clone(), SnapshotOrigin::Edit)
+            .unwrap();
+        fs::write(dir.path().join("history/2.json"), "{ half a snap").unwrap();
+
+        manager
+            .save_to_mod_file(mod_list, SnapshotOrigin::Prune)
+            .unwrap();
+        let history = manager.history().unwrap();
+        assert_eq!(
+            vec![(1, SnapshotOrigin::External), (3, SnapshotOrigin::Prune)],
+            history
+                .iter()
+                .map(|s| (s.id, s.origin.clone()))
+                .collect::<Vec<_>>()
+        );
+        let unreadable = manager.unreadable_history().unwrap();
+        assert_eq!(1, unreadable.len());
+        assert_eq!(dir.path().join("history/2.json"), unreadable[0].0);
+    }
+}
@@ -0,0 +1,179 @@
+use std::collections::HashMap;
+
+use crate::mod_data::{ApplyReport, MatchRule, ModEntry, ModList, ModProfile, ModUUID, ProfileMod};
+
+/// A mod that changed place in the load order. Positions are indices into the
+/// mod lists.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Move {
+    pub entry: ModEntry,
+    pub from: usize,
+    pub to: usize,
+}
+
+/// What changes between two mod lists.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ModListDiff {
+    pub activated: Vec<ModEntry>,
+    pub deactivated: Vec<ModEntry>,
+    /// Only the mods that were actually picked up and put elsewhere, not the
+    /// ones that merely shifted because of them.
+    pub moved: Vec<Move>,
+    /// In the old list only, e.g. pruned.
+    pub removed: Vec<ModEntry>,
+    /// In the new list only.
+    pub added: Vec<ModEntry>,
+    /// Mods the profile wants that aren't in the mod list at all.
+    pub not_installed: Vec<ProfileMod>,
+}
+
+impl ModListDiff {
+    pub fn new(before: &ModList, after: &ModList) -> Self {
+        let before_mods = before.mods();
+        let after_mods = after.mods();
+        let before_index: HashMap<&ModUUID, usize> = before_mods
+            .iter()
+            .enumerate()
+            .map(|(i, m)| (&m.uuid, i))
+            .collect();
+        let after_index: HashMap<&ModUUID, usize> = after_mods
+            .iter()
+            .enumerate()
+            .map(|(i, m)| (&m.uuid, i))
+            .collect();
+
+        let mut diff = Self {
+            removed: before_mods
+                .iter()
+                .filter(|m| !after_index.contains_key(&m.uuid))
+                .map(|&m| m.clone())
+                .collect(),
+            ..Self::default()
+        };
+
+        // (old index, new index) of every mod in both lists, in the new order
+        let mut common = Vec::new();
+        for (to, &m) in after_mods.iter().enumerate() {
+            let Some(&from) = before_index.get(&m.uuid) else {
+                diff.added.push(m.clone());
+                continue;
+            };
+            common.push((from, to));
+            match (before_mods[from].active, m.active) {
+                (false, true) => diff.activated.push(m.clone()),
+                (true, false) => diff.deactivated.push(m.clone()),
+                _ => (),
+            }
+        }
+
+        let stayed = longest_increasing(&common);
+        diff.moved = common
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !stayed.contains(i))
+            .map(|(_, &(from, to))| Move {
+                entry: after_mods[to].clone(),
+                from,
+                to,
+            })
+            .collect();
+        diff
+    }
+
+    /// Applies `profile` to a copy of `before`. Returns the diff, including
+    /// the mods it can't find, along with the report and the changed copy.
+    pub fn for_profile(
+        before: &ModList,
+        profile: &ModProfile,
+        loosest: MatchRule,
+    ) -> (Self, ApplyReport, ModList) {
+        let mut after = before.clone();
+        let report = after.apply_profile(profile.clone(), loosest);
+        let diff = Self {
+            not_installed: report.not_found.clone(),
+            ..Self::new(before, &after)
+        };
+        (diff, report, after)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+/// Indices of one longest run of `(from, to)` pairs whose `from` strictly
+/// increases: the mods that kept their order relative to each other.
+///
+/// Among equally long runs, the one with the most mods still at their old
+/// index wins, so a swap doesn't report a mod that never left its spot.
+fn longest_increasing(pairs: &[(usize, usize)]) -> Vec<usize> {
+    // Best (length, unmoved mods) of a run ending at each index, and the index before it
+    let unmoved = |i: usize| usize::from(pairs[i].0 == pairs[i].1);
+    let mut scores: Vec<(usize, usize)> = (0..pairs.len()).map(|i| (1, unmoved(i))).collect();
+    let mut previous = vec![None; pairs.len()];
+    for i in 0..pairs.len() {
+        for j in 0..i {
+            let score = (scores[j].0 + 1, scores[j].1 + unmoved(i));
+            if pairs[j].0 < pairs[i].0 && score >= scores[i] {
+                scores[i] = score;
+                previous[i] = Some(j);
+            }
+        }
+    }
+    let mut run = Vec::new();
+    let mut next = (0..pairs.len()).max_by_key(|&i| scores[i]);
+    while let Some(i) = next {
+        run.push(i);
+        next = previous[i];
+    }
+    run.reverse();
+    run
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{longest_increasing, ModListDiff};
+    use crate::mod_data::{MatchRule, ModEntry, ModFileDTO, ModList, ModProfile, ProfileMod};
+
+    const LAUNCHER_FILE: &str = include_str!("../fixtures/20190104-moddata.dat");
+
+    fn names(mods: &[ModEntry]) -> Vec<&str> {
+        mods.iter().map(|m| m.name.as_str()).collect()
+    }
+
+    #[test]
+    fn finds_the_mods_that_moved() {
+        let pairs = |from: &[usize]| from.iter().copied().zip(0..).collect::<Vec<_>>();
+        assert_eq!(vec![0, 1, 3], longest_increasing(&pairs(&[0, 1, 4, 2])));
+        // Swapping the ends leaves the middle alone
+        assert_eq!(vec![1], longest_increasing(&pairs(&[2, 1, 0])));
+        assert_eq!(Vec::<usize>::new(), longest_increasing(&[]));
+    }
+
+    #[test]
+    fn diffs_applying_a_profile() {
+        let before: ModList = serde_json::from_str::<ModFileDTO>(LAUNCHER_FILE)
+            .unwrap()
+            .into();
+        let profile = ModProfile::new(
+            "spanners".to_string(),
+            vec![ProfileMod::new("spanners"), ProfileMod::new("gretchin")],
+        );
+
+        let (diff, report, after) = ModListDiff::for_profile(&before, &profile, MatchRule::Uuid);
+        assert_eq!(vec!["Mork's Spanners"], names(&diff.activated));
+        assert_eq!(
+            vec!["Ürk Boyz Overhaul", "Old World Stuff"],
+            names(&diff.deactivated)
+        );
+        assert_eq!(1, diff.moved.len());
+        assert_eq!("Mork's Spanners", diff.moved[0].entry.name);
+        assert_eq!((1, 0), (diff.moved[0].from, diff.moved[0].to));
+        assert_eq!(vec![ProfileMod::new("gretchin")], diff.not_installed);
+        assert!(diff.removed.is_empty() && diff.added.is_empty());
+        assert_eq!(diff.not_installed, report.not_found);
+        assert_eq!("Mork's Spanners", after.mods()[0].name);
+
+        assert!(ModListDiff::new(&before, &before).is_empty());
+    }
+}
@@ -0,0 +1,342 @@
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use crate::{
+    launchers::{Launcher, WinePrefix},
+    mod_data::ModFileDTO,
+    steam::{self, SteamLibrary, TWWH3_APP_ID},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SteamInstallKind {
+    Native,
+    Flatpak,
+    Snap,
+}
+
+/// Where a candidate mod file location came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModFileSource {
+    WorkingDir,
+    Steam(SteamInstallKind),
+    WinePrefix { launcher: Launcher, name: String },
+    AppData,
+}
+
+impl fmt::Display for ModFileSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ModFileSource::WorkingDir => write!(f, "working directory"),
+            ModFileSource::Steam(SteamInstallKind::Native) => write!(f, "Steam"),
+            ModFileSource::Steam(SteamInstallKind::Flatpak) => write!(f, "Steam (Flatpak)"),
+            ModFileSource::Steam(SteamInstallKind::Snap) => write!(f, "Steam (Snap)"),
+            ModFileSource::WinePrefix { launcher, name } => write!(f, "{launcher} ({name})"),
+            ModFileSource::AppData => write!(f, "AppData"),
+        }
+    }
+}
+
+/// Why a candidate was not used.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Rejection {
+    /// The location was checked, but there is no mod file there.
+    NoModFile,
+    /// There is no Steam install at this root.
+    NoSteamRoot,
+    /// The launcher points at a Wine prefix that has no users in it.
+    NoWinePrefix,
+    /// The Steam install exists, but its library list could not be read.
+    UnreadableLibraryFolders(String),
+    /// None of the Steam install's libraries have the game.
+    GameNotInstalled,
+    /// Same location as an earlier candidate, e.g. through a symlink.
+    DuplicateOf(PathBuf),
+}
+
+impl fmt::Display for Rejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Rejection::NoModFile => write!(f, "no mod file there"),
+            Rejection::NoSteamRoot => write!(f, "no Steam install there"),
+            Rejection::NoWinePrefix => write!(f, "no Wine prefix there"),
+            Rejection::UnreadableLibraryFolders(e) => {
+                write!(f, "could not read library folders: {e}")
+            }
+            Rejection::GameNotInstalled => write!(f, "game not installed in any library"),
+            Rejection::DuplicateOf(p) => write!(f, "same as {}", p.display()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Candidate {
+    pub source: ModFileSource,
+    /// The mod file path, or the Steam root if we never got as far as a mod file.
+    pub path: PathBuf,
+    pub rejection: Option<Rejection>,
+}
+
+/// A mod file that exists, with enough detail to tell it apart from the others.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModFileInfo {
+    pub path: PathBuf,
+    pub source: ModFileSource,
+    pub modified: Option<SystemTime>,
+    /// `None` if the file could not be parsed.
+    pub mod_count: Option<usize>,
+}
+
+/// Every location checked while looking for the mod file, in priority order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DiscoveryReport {
+    pub candidates: Vec<Candidate>,
+}
+
+impl DiscoveryReport {
+    /// The first candidate that was not rejected.
+    pub fn found(&self) -> Option<&Candidate> {
+        self.candidates.iter().find(|c| c.rejection.is_none())
+    }
+
+    /// Every candidate that was not rejected, with details to tell them apart.
+    pub fn mod_files(&self) -> Vec<ModFileInfo> {
+        self.candidates
+            .iter()
+            .filter(|c| c.rejection.is_none())
+            .map(|c| {
+                let modified = fs::metadata(&c.path).and_then(|m| m.modified()).ok();
+                let mod_count = fs::read_to_string(&c.path)
+                    .ok()
+                    .and_then(|d| serde_json::from_str::<ModFileDTO>(&d).ok())
+                    .map(|dto| dto.0.len());
+                ModFileInfo {
+                    path: c.path.clone(),
+                    source: c.source.clone(),
+                    modified,
+                    mod_count,
+                }
+            })
+            .collect()
+    }
+
+    fn push(&mut self, source: ModFileSource, path: PathBuf, rejection: Option<Rejection>) {
+        self.candidates.push(Candidate {
+            source,
+            path,
+            rejection,
+        })
+    }
+
+    fn seen(&self, path: &Path) -> Option<PathBuf> {
+        let canonical = path.canonicalize().ok()?;
+        self.candidates
+            .iter()
+            .find(|c| c.path.canonicalize().ok().as_ref() == Some(&canonical))
+            .map(|c| c.path.clone())
+    }
+
+    /// Checks a plain mod file location.
+    pub fn check_file(&mut self, source: ModFileSource, path: PathBuf) {
+        let rejection = if let Some(earlier) = self.seen(&path) {
+            Some(Rejection::DuplicateOf(earlier))
+        } else if path.is_file() {
+            None
+        } else {
+            Some(Rejection::NoModFile)
+        };
+        self.push(source, path, rejection)
+    }
+
+    /// Checks a Steam install for the game's Proton prefix.
+    pub fn check_steam_root(&mut self, kind: SteamInstallKind, root: PathBuf) {
+        let source = ModFileSource::Steam(kind);
+        if !root.is_dir() {
+            return self.push(source, root, Some(Rejection::NoSteamRoot));
+        }
+        if let Some(earlier) = self.seen(&root) {
+            return self.push(source, root, Some(Rejection::DuplicateOf(earlier)));
+        }
+        match steam::find_app_library(&root, TWWH3_APP_ID) {
+            Ok(Some(library)) => self.check_file(source, library.proton_mod_file_path()),
+            Ok(None) => self.push(source, root, Some(Rejection::GameNotInstalled)),
+            Err(e) => {
+                let rejection = Rejection::UnreadableLibraryFolders(format!("{e:#}"));
+                self.push(source.clone(), root.clone(), Some(rejection));
+                // The game may still live in the root library
+                let library = SteamLibrary {
+                    path: root,
+                    apps: vec![],
+                };
+                self.check_file(source, library.proton_mod_file_path())
+            }
+        }
+    }
+
+    /// Checks every Wine user in a launcher's prefix for the mod file.
+    pub fn check_wine_prefix(&mut self, prefix: WinePrefix) {
+        let source = ModFileSource::WinePrefix {
+            launcher: prefix.launcher,
+            name: prefix.name.clone(),
+        };
+        let paths = prefix.mod_file_paths();
+        if paths.is_empty() {
+            return self.push(source, prefix.path, Some(Rejection::NoWinePrefix));
+        }
+        for path in paths {
+            self.check_file(source.clone(), path)
+        }
+    }
+}
+
+impl fmt::Display for DiscoveryReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for c in &self.candidates {
+            match &c.rejection {
+                None => writeln!(f, "[found] {}: {}", c.source, c.path.display())?,
+                Some(r) => writeln!(f, "[no] {}: {} ({r})", c.source, c.path.display())?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Known places a Linux Steam install can live, most common first.
+pub fn linux_steam_roots(home: &Path) -> Vec<(SteamInstallKind, PathBuf)> {
+    let flatpak = home.join(".var/app/com.valvesoftware.Steam");
+    let snap = home.join("snap/steam/common");
+    vec![
+        (SteamInstallKind::Native, home.join(".steam/steam")),
+        (SteamInstallKind::Native, home.join(".steam/root")),
+        (SteamInstallKind::Native, home.join(".local/share/Steam")),
+        (
+            SteamInstallKind::Flatpak,
+            flatpak.join(".local/share/Steam"),
+        ),
+        (SteamInstallKind::Flatpak, flatpak.join(".steam/steam")),
+        (SteamInstallKind::Flatpak, flatpak.join("data/Steam")),
+        (SteamInstallKind::Snap, snap.join(".local/share/Steam")),
+        (SteamInstallKind::Snap, snap.join(".steam/steam")),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::{linux_steam_roots, DiscoveryReport, ModFileSource, Rejection, SteamInstallKind};
+    use crate::launchers::Launcher;
+
+    #[test]
+    fn finds_flatpak_install_and_explains_the_rest() {
+        let home = tempfile::tempdir().unwrap();
+        let home = home.path();
+
+        // Native Steam without the game
+        let native = home.join(".local/share/Steam");
+        fs::create_dir_all(native.join("steamapps")).unwrap();
+        fs::write(
+            native.join("steamapps/libraryfolders.vdf"),
+            "\"libraryfolders\" {}",
+        )
+        .unwrap();
+
+        // Flatpak Steam with the game, but an unreadable library list
+        let flatpak = home.join(".var/app/com.valvesoftware.Steam/.local/share/Steam");
+        let mod_file = flatpak.join(
+            "steamapps/compatdata/1142710/pfx/drive_c/users/steamuser/AppData/Roaming/The Creative Assembly/Launcher/20190104-moddata.dat",
+        );
+        fs::create_dir_all(mod_file.parent().unwrap()).unwrap();
+        fs::write(&mod_file, "[]").unwrap();
+
+        let mut report = DiscoveryReport::default();
+        for (kind, root) in linux_steam_roots(home) {
+            report.check_steam_root(kind, root);
+        }
+
+        let found = report.found().unwrap();
+        assert_eq!(
+            ModFileSource::Steam(SteamInstallKind::Flatpak),
+            found.source
+        );
+        assert_eq!(mod_file, found.path);
+
+        let rejection_for = |p| {
+            report
+                .candidates
+                .iter()
+                .find(|c| c.path == p)
+                .and_then(|c| c.rejection.clone())
+        };
+        assert_eq!(
+            Some(Rejection::GameNotInstalled),
+            rejection_for(native.clone())
+        );
+        assert_eq!(
+            Some(Rejection::NoSteamRoot),
+            rejection_for(home.join("snap/steam/common/.local/share/Steam"))
+        );
+        assert!(matches!(
+            rejection_for(flatpak),
+            Some(Rejection::UnreadableLibraryFolders(_))
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinked_roots_are_only_checked_once() {
+        let home = tempfile::tempdir().unwrap();
+        let home = home.path();
+        let native = home.join(".local/share/Steam");
+        fs::create_dir_all(&native).unwrap();
+        fs::create_dir_all(home.join(".steam")).unwrap();
+        std::os::unix::fs::symlink(&native, home.join(".steam/steam")).unwrap();
+
+        let mut report = DiscoveryReport::default();
+        report.check_steam_root(SteamInstallKind::Native, home.join(".steam/steam"));
+        report.check_steam_root(SteamInstallKind::Native, native);
+
+        assert_eq!(
+            Some(Rejection::DuplicateOf(home.join(".steam/steam"))),
+            report.candidates.last().unwrap().rejection
+        );
+    }
+
+    #[test]
+    fn lists_found_mod_files_with_details() {
+        let dir = tempfile::tempdir().unwrap();
+        let good = dir.path().join("good.dat");
+        let entry = |uuid| {
+            format!(
+                r#"{{"uuid":"{uuid}","name":"{uuid}","active":true,"category":"mod","game":"warhammer3","order":1,"owned":true,"packfile":"Z:/{uuid}.pack","short":""}}"#
+            )
+        };
+        fs::write(&good, format!("[{},{}]", entry("a"), entry("b"))).unwrap();
+        let broken = dir.path().join("broken.dat");
+        fs::write(&broken, "not json").unwrap();
+
+        let mut report = DiscoveryReport::default();
+        report.check_file(ModFileSource::WorkingDir, dir.path().join("gone.dat"));
+        report.check_file(ModFileSource::WorkingDir, good.clone());
+        let bottle = ModFileSource::WinePrefix {
+            launcher: Launcher::Bottles,
+            name: "Epic".to_string(),
+        };
+        report.check_file(bottle.clone(), broken.clone());
+
+        let files = report.mod_files();
+        assert_eq!(
+            vec![
+                (good, ModFileSource::WorkingDir, Some(2)),
+                (broken, bottle, None)
+            ],
+            files
+                .iter()
+                .map(|f| (f.path.clone(), f.source.clone(), f.mod_count))
+                .collect::<Vec<_>>()
+        );
+        assert!(files.iter().all(|f| f.modified.is_some()));
+    }
+}
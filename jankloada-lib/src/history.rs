@@ -0,0 +1,127 @@
+use std::fmt;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::mod_data::ModList;
+
+/// What caused a mod list to be written.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SnapshotOrigin {
+    /// A profile was applied.
+    Profile { name: String },
+    /// Mods were changed by hand.
+    Edit,
+    /// Mods with missing pack files were removed.
+    Prune,
+    /// Someone else (usually the launcher) changed the file since we last wrote it.
+    External,
+    /// An older snapshot was restored.
+    Restore { id: u64 },
+    /// Went one step back, to a copy of snapshot `restored`.
+    Undo { restored: u64 },
+}
+
+impl SnapshotOrigin {
+    /// Short, stable name of the variant, as used in the history files.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            SnapshotOrigin::Profile { .. } => "profile",
+            SnapshotOrigin::Edit => "edit",
+            SnapshotOrigin::Prune => "prune",
+            SnapshotOrigin::External => "external",
+            SnapshotOrigin::Restore { .. } => "restore",
+            SnapshotOrigin::Undo { .. } => "undo",
+        }
+    }
+}
+
+impl fmt::Display for SnapshotOrigin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotOrigin::Profile { name } => write!(f, "applied profile \"{name}\""),
+            SnapshotOrigin::Edit => write!(f, "edited"),
+            SnapshotOrigin::Prune => write!(f, "removed missing mods"),
+            SnapshotOrigin::External => write!(f, "changed outside jankloada"),
+            SnapshotOrigin::Restore { id } => write!(f, "restored #{id}"),
+            SnapshotOrigin::Undo { restored } => write!(f, "undo, back to #{restored}"),
+        }
+    }
+}
+
+/// A mod list as it was written to the mod file.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Snapshot {
+    pub id: u64,
+    pub created: DateTime<Utc>,
+    pub origin: SnapshotOrigin,
+    pub mod_list: ModList,
+}
+
+/// The snapshot to go back to from the newest one in `history` (oldest first).
+///
+/// Undoing an undo keeps walking backwards rather than flipping between two states.
+pub fn undo_target(history: &[Snapshot]) -> Option<&Snapshot> {
+    let newest = history.last()?;
+    let current = match newest.origin {
+        SnapshotOrigin::Undo { restored } => history.iter().position(|s| s.id == restored)?,
+        _ => history.len() - 1,
+    };
+    current.checked_sub(1).map(|i| &history[i])
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use super::{undo_target, Snapshot, SnapshotOrigin};
+    use crate::mod_data::{ModFileDTO, ModList};
+
+    fn snapshot(id: u64, origin: SnapshotOrigin) -> Snapshot {
+        let mod_list: ModList = ModFileDTO(vec![]).into();
+        Snapshot {
+            id,
+            created: Utc::now(),
+            origin,
+            mod_list,
+        }
+    }
+
+    #[test]
+    fn undo_walks_backwards() {
+        let profile = |n: &str| SnapshotOrigin::Profile {
+            name: n.to_string(),
+        };
+        let mut history = vec![
+            snapshot(1, profile("a")),
+            snapshot(2, profile("b")),
+            snapshot(3, SnapshotOrigin::Edit),
+        ];
+        assert_eq!(2, undo_target(&history).unwrap().id);
+
+        history.push(snapshot(4, SnapshotOrigin::Undo { restored: 2 }));
+        assert_eq!(1, undo_target(&history).unwrap().id);
+
+        history.push(snapshot(5, SnapshotOrigin::Undo { restored: 1 }));
+        assert!(undo_target(&history).is_none());
+        assert!(undo_target(&[]).is_none());
+    }
+
+    #[test]
+    fn kind_matches_serialized_tag() {
+        for origin in [
+            SnapshotOrigin::Profile {
+                name: "a".to_string(),
+            },
+            SnapshotOrigin::Edit,
+            SnapshotOrigin::Prune,
+            SnapshotOrigin::External,
+            SnapshotOrigin::Restore { id: 1 },
+            SnapshotOrigin::Undo { restored: 1 },
+        ] {
+            let value = serde_json::to_value(&origin).unwrap();
+            assert_eq!(origin.kind(), value["kind"]);
+        }
+    }
+}
@@ -0,0 +1,263 @@
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use crate::data_manager::{CA_LAUNCHER_DIR, CA_MOD_FILE};
+
+/// Non-Steam launchers that run the Epic copy of the game through Wine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Launcher {
+    Heroic,
+    Lutris,
+    Bottles,
+}
+
+impl fmt::Display for Launcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Launcher::Heroic => write!(f, "Heroic"),
+            Launcher::Lutris => write!(f, "Lutris"),
+            Launcher::Bottles => write!(f, "Bottles"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WinePrefix {
+    pub launcher: Launcher,
+    /// The game or bottle the prefix belongs to, as the launcher names it.
+    pub name: String,
+    pub path: PathBuf,
+}
+
+impl WinePrefix {
+    /// Every place the CA launcher could have put its mod file, one per Wine user.
+    /// Proton prefixes keep the Windows drive one level down, in `pfx`.
+    pub fn mod_file_paths(&self) -> Vec<PathBuf> {
+        let mut paths: Vec<PathBuf> = ["drive_c/users", "pfx/drive_c/users"]
+            .iter()
+            .flat_map(|users| fs::read_dir(self.path.join(users)).into_iter().flatten())
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_name() != "Public")
+            .map(|e| e.path().join(CA_LAUNCHER_DIR).join(CA_MOD_FILE))
+            .collect();
+        paths.sort();
+        paths
+    }
+}
+
+/// All prefixes known to Heroic, Lutris and Bottles, native and Flatpak.
+pub fn wine_prefixes(home: &Path) -> Vec<WinePrefix> {
+    let mut prefixes = heroic_prefixes(home);
+    prefixes.append(&mut lutris_prefixes(home));
+    prefixes.append(&mut bottles_prefixes(home));
+    prefixes
+}
+
+fn config_files(dir: &Path, extension: &str) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.extension().map(|e| e == extension).unwrap_or(false))
+        .collect();
+    files.sort();
+    files
+}
+
+fn expand_home(path: &str, home: &Path) -> PathBuf {
+    match path.strip_prefix("~/") {
+        Some(rest) => home.join(rest),
+        None => PathBuf::from(path),
+    }
+}
+
+/// Heroic keeps one `GamesConfig/<app name>.json` per game, keyed by app name.
+pub fn heroic_prefixes(home: &Path) -> Vec<WinePrefix> {
+    [
+        home.join(".config/heroic"),
+        home.join(".var/app/com.heroicgameslauncher.hgl/config/heroic"),
+    ]
+    .iter()
+    .flat_map(|dir| config_files(&dir.join("GamesConfig"), "json"))
+    .filter_map(|f| fs::read_to_string(f).ok())
+    .filter_map(|data| serde_json::from_str::<serde_json::Value>(&data).ok())
+    .flat_map(|config| {
+        config
+            .as_object()
+            .into_iter()
+            .flatten()
+            .filter_map(|(app, settings)| {
+                let prefix = settings.get("winePrefix")?.as_str()?;
+                Some(WinePrefix {
+                    launcher: Launcher::Heroic,
+                    name: app.clone(),
+                    path: expand_home(prefix, home),
+                })
+            })
+            .collect::<Vec<_>>()
+    })
+    .collect()
+}
+
+/// Lutris keeps one `games/<slug>.yml` per game, with the prefix under `game.prefix`.
+pub fn lutris_prefixes(home: &Path) -> Vec<WinePrefix> {
+    [
+        home.join(".config/lutris"),
+        home.join(".local/share/lutris"),
+        home.join(".var/app/net.lutris.Lutris/config/lutris"),
+        home.join(".var/app/net.lutris.Lutris/data/lutris"),
+    ]
+    .iter()
+    .flat_map(|dir| config_files(&dir.join("games"), "yml"))
+    .filter_map(|f| {
+        let data = fs::read_to_string(&f).ok()?;
+        let config: serde_yaml::Value = serde_yaml::from_str(&data).ok()?;
+        let prefix = config.get("game")?.get("prefix")?.as_str()?;
+        Some(WinePrefix {
+            launcher: Launcher::Lutris,
+            name: f.file_stem()?.to_string_lossy().to_string(),
+            path: expand_home(prefix, home),
+        })
+    })
+    .collect()
+}
+
+/// Every Bottles bottle is a prefix, marked by its `bottle.yml`.
+pub fn bottles_prefixes(home: &Path) -> Vec<WinePrefix> {
+    let mut bottles: Vec<WinePrefix> = [
+        home.join(".local/share/bottles/bottles"),
+        home.join(".var/app/com.usebottles.bottles/data/bottles/bottles"),
+    ]
+    .iter()
+    .flat_map(|dir| fs::read_dir(dir).into_iter().flatten())
+    .filter_map(|e| e.ok())
+    .map(|e| e.path())
+    .filter(|p| p.join("bottle.yml").is_file())
+    .map(|p| WinePrefix {
+        launcher: Launcher::Bottles,
+        name: p
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        path: p,
+    })
+    .collect();
+    bottles.sort_by(|a, b| a.path.cmp(&b.path));
+    bottles
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::Path};
+
+    use super::{wine_prefixes, Launcher, WinePrefix};
+
+    fn write(path: &Path, contents: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    #[test]
+    fn finds_prefixes_of_all_launchers() {
+        let home = tempfile::tempdir().unwrap();
+        let home = home.path();
+
+        write(
+            &home.join(".config/heroic/GamesConfig/Quail.json"),
+            r#"{
+                "Quail": {
+                    "winePrefix": "~/Games/Heroic/Prefixes/default/Warhammer3",
+                    "wineVersion": { "type": "proton" }
+                },
+                "version": "v0",
+                "explicit": true
+            }"#,
+        );
+        write(
+            &home.join(".local/share/lutris/games/total-war-warhammer-iii-1681234567.yml"),
+            &format!(
+                "game:\n  exe: drive_c/Games/Warhammer3/Warhammer3.exe\n  prefix: {}\nwine:\n  version: lutris-GE\n",
+                home.join("Games/epic").display()
+            ),
+        );
+        write(
+            &home.join(".var/app/com.usebottles.bottles/data/bottles/bottles/Epic/bottle.yml"),
+            "Name: Epic\n",
+        );
+        // Not a bottle
+        fs::create_dir_all(home.join(".local/share/bottles/bottles/runners")).unwrap();
+
+        let prefixes = wine_prefixes(home);
+        assert_eq!(
+            vec![
+                WinePrefix {
+                    launcher: Launcher::Heroic,
+                    name: "Quail".to_string(),
+                    path: home.join("Games/Heroic/Prefixes/default/Warhammer3"),
+                },
+                WinePrefix {
+                    launcher: Launcher::Lutris,
+                    name: "total-war-warhammer-iii-1681234567".to_string(),
+                    path: home.join("Games/epic"),
+                },
+                WinePrefix {
+                    launcher: Launcher::Bottles,
+                    name: "Epic".to_string(),
+                    path: home.join(".var/app/com.usebottles.bottles/data/bottles/bottles/Epic"),
+                },
+            ],
+            prefixes
+        );
+    }
+
+    #[test]
+    fn lists_mod_file_per_wine_user() {
+        let prefix = tempfile::tempdir().unwrap();
+        for user in ["Public", "steamuser", "gretchin"] {
+            fs::create_dir_all(prefix.path().join("drive_c/users").join(user)).unwrap();
+        }
+        let prefix = WinePrefix {
+            launcher: Launcher::Heroic,
+            name: "Quail".to_string(),
+            path: prefix.path().to_path_buf(),
+        };
+
+        let launcher_dir = "AppData/Roaming/The Creative Assembly/Launcher/20190104-moddata.dat";
+        assert_eq!(
+            vec![
+                prefix
+                    .path
+                    .join("drive_c/users/gretchin")
+                    .join(launcher_dir),
+                prefix
+                    .path
+                    .join("drive_c/users/steamuser")
+                    .join(launcher_dir),
+            ],
+            prefix.mod_file_paths()
+        );
+    }
+
+    #[test]
+    fn lists_mod_file_in_proton_prefix() {
+        let prefix = tempfile::tempdir().unwrap();
+        for user in ["Public", "steamuser"] {
+            fs::create_dir_all(prefix.path().join("pfx/drive_c/users").join(user)).unwrap();
+        }
+        let prefix = WinePrefix {
+            launcher: Launcher::Heroic,
+            name: "Quail".to_string(),
+            path: prefix.path().to_path_buf(),
+        };
+
+        assert_eq!(
+            vec![prefix.path.join(
+                "pfx/drive_c/users/steamuser/AppData/Roaming/The Creative Assembly/Launcher/20190104-moddata.dat"
+            )],
+            prefix.mod_file_paths()
+        );
+    }
+}
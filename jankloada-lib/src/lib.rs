@@ -1,2 +1,12 @@
+pub mod conflicts;
 pub mod data_manager;
+pub mod diff;
+pub mod discovery;
+pub mod history;
+pub mod launchers;
 pub mod mod_data;
+pub mod pack;
+pub mod settings;
+pub mod share;
+pub mod steam;
+pub mod vdf;
@@ -1,25 +1,142 @@
-use std::path::Path;
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+};
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use thiserror::Error;
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
 
-#[derive(Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Debug, Clone)]
+#[derive(Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone, Default)]
 pub struct ModUUID(pub String);
 
+/// The launcher's mod file. It is a bare array, so every field lives in the entries.
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ModFileDTO(pub Vec<ModEntryDTO>);
 
-#[derive(Serialize, Deserialize, Debug)]
+/// One entry in the launcher's mod file.
+///
+/// Keeps every field the launcher wrote, in the order it wrote them, so that
+/// fields we don't know about survive a save untouched.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(try_from = "Map<String, Value>", into = "Map<String, Value>")]
 pub struct ModEntryDTO {
-    uuid: ModUUID,
-    name: String,
+    fields: ModEntryFields,
+    launcher_fields: LauncherFields,
+}
+
+/// The fields of a launcher mod entry that we understand. Anything else ends
+/// up in `LauncherFields`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+struct ModEntryFields {
     active: bool,
     category: String,
     game: String,
+    name: String,
     order: usize,
     owned: bool,
     packfile: String,
     short: String,
+    uuid: ModUUID,
+}
+
+impl ModEntryFields {
+    fn to_map(&self) -> Map<String, Value> {
+        match serde_json::to_value(self) {
+            Ok(Value::Object(fields)) => fields,
+            _ => Map::new(),
+        }
+    }
+}
+
+impl TryFrom<Map<String, Value>> for ModEntryDTO {
+    type Error = serde_json::Error;
+
+    fn try_from(entry: Map<String, Value>) -> Result<Self, Self::Error> {
+        Ok(Self {
+            fields: serde_json::from_value(Value::Object(entry.clone()))?,
+            launcher_fields: entry.into(),
+        })
+    }
+}
+
+impl From<ModEntryDTO> for Map<String, Value> {
+    fn from(dto: ModEntryDTO) -> Self {
+        dto.launcher_fields.merge(dto.fields.to_map())
+    }
+}
+
+/// Fields of a mod file entry we don't understand, to be written back as-is,
+/// in the spot the launcher had them.
+///
+/// Stored as one map with the known fields as nulls, so snapshots keep the
+/// order too.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(from = "Map<String, Value>", into = "Map<String, Value>")]
+pub struct LauncherFields {
+    unknown: Map<String, Value>,
+    /// Every key of the entry, known ones included, in the launcher's order.
+    order: Vec<String>,
+}
+
+impl LauncherFields {
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.unknown.get(key)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Value)> {
+        self.unknown.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.unknown.is_empty()
+    }
+
+    /// `known` and the unknown fields, in the launcher's order. Keys the
+    /// launcher didn't write go last.
+    fn merge(mut self, mut known: Map<String, Value>) -> Map<String, Value> {
+        let mut entry: Map<String, Value> = self
+            .order
+            .iter()
+            .filter_map(|k| {
+                let value = known.remove(k).or_else(|| self.unknown.remove(k))?;
+                Some((k.clone(), value))
+            })
+            .collect();
+        entry.extend(known);
+        entry.extend(self.unknown);
+        entry
+    }
+}
+
+impl From<Map<String, Value>> for LauncherFields {
+    fn from(entry: Map<String, Value>) -> Self {
+        let known = ModEntryFields::default().to_map();
+        Self {
+            order: entry.keys().cloned().collect(),
+            unknown: entry
+                .into_iter()
+                .filter(|(k, _)| !known.contains_key(k))
+                .collect(),
+        }
+    }
+}
+
+impl From<LauncherFields> for Map<String, Value> {
+    fn from(fields: LauncherFields) -> Self {
+        let mut unknown = fields.unknown;
+        fields
+            .order
+            .into_iter()
+            .map(|k| {
+                let value = unknown.remove(&k).unwrap_or(Value::Null);
+                (k, value)
+            })
+            .collect()
+    }
 }
 
 impl From<ModList> for ModFileDTO {
@@ -30,22 +147,25 @@ impl From<ModList> for ModFileDTO {
                 .into_iter()
                 .enumerate()
                 .map(|(i, m)| ModEntryDTO {
-                    uuid: m.uuid,
-                    active: m.active,
-                    category: m.category,
-                    game: m.game,
-                    name: m.name,
-                    order: i + 1, // Launcher treats 0 as "last"
-                    owned: m.owned,
-                    packfile: m.packfile,
-                    short: m.short,
+                    fields: ModEntryFields {
+                        uuid: m.uuid,
+                        active: m.active,
+                        category: m.category,
+                        game: m.game,
+                        name: m.name,
+                        order: i + 1, // Launcher treats 0 as "last"
+                        owned: m.owned,
+                        packfile: m.packfile,
+                        short: m.short,
+                    },
+                    launcher_fields: m.launcher_fields,
                 })
                 .collect(),
         )
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct ModEntry {
     pub uuid: ModUUID,
     pub name: String,
@@ -55,36 +175,177 @@ pub struct ModEntry {
     pub owned: bool,
     pub packfile: String,
     pub short: String,
+    /// Fields from the mod file we don't understand, to be written back as-is.
+    #[serde(default)]
+    pub launcher_fields: LauncherFields,
 }
 
 impl ModEntry {
     pub fn file_exists(&self) -> bool {
+        self.packfile_path().exists()
+    }
+
+    /// Where the pack file is on this machine.
+    pub fn packfile_path(&self) -> &Path {
         // Wine/Proton workaround hack.
         let path = if self.packfile.starts_with("Z:/") {
             &self.packfile[2..]
         } else {
             self.packfile.as_str()
         };
-        Path::new(path).exists()
+        Path::new(path)
     }
 
     fn set_active(&mut self, t: bool) {
         self.active = t
     }
+
+    /// The pack file's name, without its directory.
+    pub fn packfile_name(&self) -> &str {
+        self.packfile
+            .rsplit(['/', '\\'])
+            .next()
+            .unwrap_or(&self.packfile)
+    }
+
+    /// The Steam workshop item the pack file came from, if it did.
+    pub fn workshop_id(&self) -> Option<&str> {
+        // .../steamapps/workshop/content/<app id>/<workshop id>/<pack>
+        let mut parts = self.packfile.split(['/', '\\']);
+        parts.find(|&p| p == "content")?;
+        parts.next()?;
+        parts
+            .next()
+            .filter(|id| !id.is_empty() && id.chars().all(|c| c.is_ascii_digit()))
+    }
+
+    /// The picture shipped next to the pack file: one with the same name, or
+    /// for workshop mods, whichever `.png` is in the item's folder.
+    pub fn preview_image(&self) -> Option<PathBuf> {
+        let pack = self.packfile_path();
+        let same_name = pack.with_extension("png");
+        if same_name.is_file() {
+            return Some(same_name);
+        }
+        // Other folders, like the game's data folder, have every mod's pictures in them
+        self.workshop_id()?;
+        let mut pngs: Vec<_> = fs::read_dir(pack.parent()?)
+            .ok()?
+            .filter_map(|e| Some(e.ok()?.path()))
+            .filter(|p| p.extension().is_some_and(|e| e.eq_ignore_ascii_case("png")))
+            .collect();
+        pngs.sort();
+        pngs.into_iter().next()
+    }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct ModList(Vec<ModEntry>);
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ModList(pub(crate) Vec<ModEntry>);
+
+/// What `ModList::apply_profile` did besides activating and ordering the profile's mods.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ApplyReport {
+    /// How each profile mod that was found got matched to a mod in the list.
+    pub matched: Vec<ProfileMatch>,
+    /// Profile mods that aren't in the mod list.
+    pub not_found: Vec<ProfileMod>,
+    /// Mods that were active, but aren't in the profile.
+    pub deactivated: Vec<ModEntry>,
+    /// Profile mods that could not be put in the profile's order (e.g. because
+    /// the mod list has them twice), and were appended after the ordered ones.
+    pub unordered: Vec<ModEntry>,
+}
+
+/// A profile mod, and the mod in the list it was matched to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProfileMatch {
+    pub wanted: ProfileMod,
+    pub uuid: ModUUID,
+    pub rule: MatchRule,
+}
+
+/// Ways to tell that a profile mod is a mod in the list, strictest first.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchRule {
+    /// Same launcher uuid.
+    Uuid,
+    /// Same Steam workshop item, e.g. re-subscribed.
+    WorkshopId,
+    /// Same pack file name, e.g. a local copy of a workshop mod.
+    Packfile,
+}
+
+impl MatchRule {
+    pub const ALL: [MatchRule; 3] = [MatchRule::Uuid, MatchRule::WorkshopId, MatchRule::Packfile];
+
+    fn matches(self, wanted: &ProfileMod, entry: &ModEntry) -> bool {
+        match self {
+            MatchRule::Uuid => wanted.uuid == entry.uuid,
+            MatchRule::WorkshopId => {
+                wanted.workshop_id.is_some() && wanted.workshop_id.as_deref() == entry.workshop_id()
+            }
+            MatchRule::Packfile => wanted
+                .packfile
+                .as_deref()
+                .map(|p| p.eq_ignore_ascii_case(entry.packfile_name()))
+                .unwrap_or(false),
+        }
+    }
+}
+
+impl fmt::Display for MatchRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MatchRule::Uuid => write!(f, "uuid"),
+            MatchRule::WorkshopId => write!(f, "workshop id"),
+            MatchRule::Packfile => write!(f, "pack file name"),
+        }
+    }
+}
+
+/// Why a `ModList` operation could not be done. The list is left untouched.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ModListError {
+    #[error("No mod at position {index}, there are only {len}")]
+    IndexOutOfRange { index: usize, len: usize },
+    #[error("No mod with uuid \"{}\"", .0.0)]
+    UnknownUuid(ModUUID),
+    #[error("Can't move a mod next to itself")]
+    SameMod,
+    #[error("No mod matches \"{0}\"")]
+    NoMatch(String),
+    #[error("\"{query}\" matches more than one mod: {}", .names.join(", "))]
+    Ambiguous { query: String, names: Vec<String> },
+}
+
+/// What `ModList::prune_missing` took out.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PruneReport {
+    pub removed: Vec<ModEntry>,
+    /// Where `DataManager::prune_mod_file` backed up the old mod file, if it did.
+    pub backup: Option<PathBuf>,
+}
 
 impl ModList {
     pub fn mods(&self) -> Vec<&ModEntry> {
         self.0.iter().collect()
     }
 
-    pub fn prune_missing(&mut self) {
-        // TODO: here we check if any mod files are missing, and if so, remove
-        // them from the mod list
-        todo!()
+    /// Removes every mod whose pack file no longer exists.
+    pub fn prune_missing(&mut self) -> PruneReport {
+        let (removed, kept) = self.0.drain(..).partition(|m| !m.file_exists());
+        self.0 = kept;
+        PruneReport {
+            removed,
+            backup: None,
+        }
+    }
+
+    /// Takes the mod at `index` out of the list.
+    pub fn remove_mod(&mut self, index: usize) -> Result<ModEntry, ModListError> {
+        self.check_index(index)?;
+        Ok(self.0.remove(index))
     }
 
     pub fn get_missing(&self) -> Vec<&ModEntry> {
@@ -95,23 +356,88 @@ impl ModList {
         self.0.iter().filter(|m| m.active).collect()
     }
 
-    pub fn deactivate_all(&mut self) {
-        self.0.iter_mut().for_each(|m| m.active = false)
+    /// Deactivates every mod, or only `game`'s if there is one.
+    pub fn deactivate_all(&mut self, game: Option<&str>) {
+        self.0
+            .iter_mut()
+            .filter(|m| game.is_none_or(|g| m.game == g))
+            .for_each(|m| m.active = false)
     }
 
-    pub fn apply_profile(&mut self, profile: ModProfile) {
-        self.deactivate_all();
+    /// Where each of `wanted` is in this list, and how it was found. Mods are
+    /// matched as loosely as `loosest` allows, stricter rules first, and each
+    /// mod here is only matched once, so two profile mods with the same pack
+    /// file name don't both get it. Only `game`'s mods are matched, if there
+    /// is one.
+    pub fn match_profile(
+        &self,
+        wanted: &[ProfileMod],
+        game: Option<&str>,
+        loosest: MatchRule,
+    ) -> Vec<Option<(usize, MatchRule)>> {
+        let mut found: Vec<Option<(usize, MatchRule)>> = vec![None; wanted.len()];
+        // Other games' mods are never up for grabs
+        let mut claimed: Vec<bool> = self
+            .0
+            .iter()
+            .map(|m| game.is_some_and(|g| m.game != g))
+            .collect();
+        for rule in MatchRule::ALL.into_iter().filter(|&rule| rule <= loosest) {
+            for (wanted, found_at) in wanted.iter().zip(found.iter_mut()) {
+                if found_at.is_some() {
+                    continue;
+                }
+                let i =
+                    (0..self.0.len()).find(|&i| !claimed[i] && rule.matches(wanted, &self.0[i]));
+                if let Some(i) = i {
+                    claimed[i] = true;
+                    *found_at = Some((i, rule));
+                }
+            }
+        }
+        found
+    }
+
+    /// Activates exactly the mods in `profile`, in its order, before all others.
+    /// Mods are matched as loosely as `loosest` allows. Other games' mods are
+    /// left alone if the profile is for one game.
+    pub fn apply_profile(&mut self, profile: ModProfile, loosest: MatchRule) -> ApplyReport {
+        let mut report = ApplyReport::default();
+        let game = profile.game.as_deref();
+        let found = self.match_profile(&profile.active_mods, game, loosest);
+        // The profile in terms of this list's uuids
+        let mut active_mods: Vec<ModUUID> = Vec::new();
+        for (wanted, found_at) in profile.active_mods.into_iter().zip(found) {
+            match found_at {
+                Some((i, rule)) => {
+                    let uuid = self.0[i].uuid.clone();
+                    active_mods.push(uuid.clone());
+                    report.matched.push(ProfileMatch { wanted, uuid, rule });
+                }
+                None => report.not_found.push(wanted),
+            }
+        }
+        report.deactivated = self
+            .0
+            .iter()
+            .filter(|m| m.active && !active_mods.contains(&m.uuid))
+            .filter(|m| game.is_none_or(|g| m.game == g))
+            .map(|m| ModEntry {
+                active: false,
+                ..m.clone()
+            })
+            .collect();
+        self.deactivate_all(game);
 
         let mut mods: Vec<ModEntry> = Vec::new();
         mods.append(&mut self.0);
 
         let (mut in_profile, mut outside_profile): (Vec<_>, _) = mods
             .into_iter()
-            .partition(|m| profile.active_mods.contains(&m.uuid));
-        in_profile.iter_mut().for_each(|mut m| m.active = true);
+            .partition(|m| active_mods.contains(&m.uuid));
+        in_profile.iter_mut().for_each(|m| m.active = true);
 
-        let mut in_profile_ordered: Vec<ModEntry> = profile
-            .active_mods
+        let mut in_profile_ordered: Vec<ModEntry> = active_mods
             .iter()
             .filter_map(|m| {
                 in_profile
@@ -123,61 +449,409 @@ impl ModList {
 
         self.0.append(&mut in_profile_ordered);
         // Should we somehow fail to order item(s), append here to avoid data loss
+        report.unordered = in_profile.clone();
         self.0.append(&mut in_profile);
         self.0.append(&mut outside_profile);
+        report
+    }
+
+    /// Index of the one mod for `game` that `query` points at: its uuid, exact
+    /// name or pack file, or else a case-insensitive match on part of its name.
+    pub fn find_mod(&self, query: &str, game: &str) -> Result<usize, ModListError> {
+        let exact = |m: &ModEntry| {
+            m.uuid.0 == query
+                || m.name == query
+                || m.packfile == query
+                || m.packfile_name() == query
+        };
+        let query_folded = fold(query);
+        let substring = |m: &ModEntry| fold(&m.name).contains(&query_folded);
+        let subsequence = |m: &ModEntry| {
+            let name = fold(&m.name);
+            let mut chars = name.chars();
+            query_folded.chars().all(|q| chars.any(|c| c == q))
+        };
+
+        let matchers: [&dyn Fn(&ModEntry) -> bool; 3] = [&exact, &substring, &subsequence];
+        for matcher in matchers {
+            let found: Vec<usize> = (0..self.0.len())
+                .filter(|&i| self.0[i].game == game && matcher(&self.0[i]))
+                .collect();
+            match found.as_slice() {
+                [] => continue,
+                [i] => return Ok(*i),
+                _ => {
+                    return Err(ModListError::Ambiguous {
+                        query: query.to_string(),
+                        names: found.iter().map(|&i| self.0[i].name.clone()).collect(),
+                    })
+                }
+            }
+        }
+        Err(ModListError::NoMatch(query.to_string()))
     }
 
-    pub fn set_mod_active_state(&mut self, index: usize, b: bool) -> Result<()> {
+    /// Index of the mod with `uuid`.
+    pub fn index_of(&self, uuid: &ModUUID) -> Result<usize, ModListError> {
         self.0
-            .get_mut(index)
-            .ok_or_else(|| anyhow!("as"))
-            .map(|m| m.set_active(b))
+            .iter()
+            .position(|m| &m.uuid == uuid)
+            .ok_or_else(|| ModListError::UnknownUuid(uuid.clone()))
+    }
+
+    fn check_index(&self, index: usize) -> Result<(), ModListError> {
+        if index < self.0.len() {
+            Ok(())
+        } else {
+            Err(ModListError::IndexOutOfRange {
+                index,
+                len: self.0.len(),
+            })
+        }
+    }
+
+    /// Moves the mod at `from` so that it ends up at index `to`.
+    pub fn move_mod(&mut self, from: usize, to: usize) -> Result<(), ModListError> {
+        self.check_index(from)?;
+        self.check_index(to)?;
+        let entry = self.0.remove(from);
+        self.0.insert(to, entry);
+        Ok(())
+    }
+
+    /// Moves mod `uuid` to right before mod `other`.
+    pub fn move_before(&mut self, uuid: &ModUUID, other: &ModUUID) -> Result<(), ModListError> {
+        self.move_next_to(uuid, other, 0)
+    }
+
+    /// Moves mod `uuid` to right after mod `other`.
+    pub fn move_after(&mut self, uuid: &ModUUID, other: &ModUUID) -> Result<(), ModListError> {
+        self.move_next_to(uuid, other, 1)
     }
+
+    fn move_next_to(
+        &mut self,
+        uuid: &ModUUID,
+        other: &ModUUID,
+        offset: usize,
+    ) -> Result<(), ModListError> {
+        let from = self.index_of(uuid)?;
+        let other = self.index_of(other)?;
+        if from == other {
+            return Err(ModListError::SameMod);
+        }
+        // Where `other` ends up once the mod is taken out
+        let other = if from < other { other - 1 } else { other };
+        self.move_mod(from, other + offset)
+    }
+
+    /// Swaps the mods at indices `a` and `b`.
+    pub fn swap(&mut self, a: usize, b: usize) -> Result<(), ModListError> {
+        self.check_index(a)?;
+        self.check_index(b)?;
+        self.0.swap(a, b);
+        Ok(())
+    }
+
+    /// Reorders the whole list by `key`. Mods with equal keys keep their order.
+    pub fn sort_by_key<K: Ord>(&mut self, key: impl FnMut(&ModEntry) -> K) {
+        self.0.sort_by_key(key)
+    }
+
+    pub fn set_mod_active_state(&mut self, index: usize, b: bool) -> Result<(), ModListError> {
+        self.check_index(index)?;
+        self.0[index].set_active(b);
+        Ok(())
+    }
+}
+
+/// Lowercases and strips accents, so "urk" finds "Ürk".
+fn fold(s: &str) -> String {
+    s.nfd()
+        .filter(|c| !is_combining_mark(*c))
+        .flat_map(char::to_lowercase)
+        .collect()
 }
 
 impl From<ModFileDTO> for ModList {
     fn from(mut dto: ModFileDTO) -> Self {
-        dto.0.sort_by_key(|m| m.order);
+        dto.0.sort_by_key(|m| m.fields.order);
         Self(
             dto.0
                 .into_iter()
-                .map(|m| ModEntry {
-                    uuid: m.uuid,
-                    active: m.active,
-                    category: m.category,
-                    game: m.game,
-                    name: m.name,
-                    owned: m.owned,
-                    packfile: m.packfile,
-                    short: m.short,
-                })
+                .map(
+                    |ModEntryDTO {
+                         fields: m,
+                         launcher_fields,
+                     }| ModEntry {
+                        uuid: m.uuid,
+                        active: m.active,
+                        category: m.category,
+                        game: m.game,
+                        name: m.name,
+                        owned: m.owned,
+                        packfile: m.packfile,
+                        short: m.short,
+                        launcher_fields,
+                    },
+                )
                 .collect(),
         )
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+/// Version of the profile format `ModProfile::from_toml` upgrades to.
+///
+/// 1. `name` and `active_mods` as bare uuids, no `version` field
+/// 2. Metadata, and `active_mods` as tables that can carry pack file names,
+///    workshop ids and notes
+pub const PROFILE_VERSION: u32 = 2;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct ModProfile {
+    /// Format version, see `PROFILE_VERSION`. Profiles from before there were
+    /// versions don't have one, which makes them version 1.
+    #[serde(default = "first_profile_version")]
+    pub version: u32,
     pub name: String,
-    pub active_mods: Vec<ModUUID>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// The launcher's game id, like in the mod file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub game: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated: Option<DateTime<Utc>>,
+    pub active_mods: Vec<ProfileMod>,
+}
+
+fn first_profile_version() -> u32 {
+    1
 }
 
 impl ModProfile {
-    pub fn new_from_mod_list(name: String, mod_list: &ModList) -> Self {
+    pub fn new(name: String, active_mods: Vec<ProfileMod>) -> Self {
         Self {
+            version: PROFILE_VERSION,
             name,
-            active_mods: mod_list
-                .get_active()
-                .iter()
-                .map(|m| m.uuid.clone())
-                .collect(),
+            description: None,
+            game: None,
+            author: None,
+            tags: vec![],
+            created: None,
+            updated: None,
+            active_mods,
+        }
+    }
+
+    /// A profile of the mods active for `game` in `mod_list`, ours or someone
+    /// else's, in its order.
+    pub fn new_from_mod_list(name: String, mod_list: &ModList, game: &str) -> Self {
+        Self::new(name, Vec::new()).with_mods_from(mod_list, game)
+    }
+
+    /// Makes the profile `game`'s and replaces its mods with the ones active
+    /// for `game` in `mod_list`, keeping the notes on mods that stay (by uuid
+    /// or workshop id).
+    pub fn with_mods_from(mut self, mod_list: &ModList, game: &str) -> Self {
+        let old_mods = std::mem::take(&mut self.active_mods);
+        self.game = Some(game.to_string());
+        self.active_mods = mod_list
+            .get_active()
+            .into_iter()
+            .filter(|m| m.game == game)
+            .map(|m| {
+                let new = ProfileMod::from(m);
+                let old = old_mods.iter().find(|o| {
+                    o.uuid == new.uuid
+                        || (o.workshop_id.is_some() && o.workshop_id == new.workshop_id)
+                });
+                ProfileMod {
+                    note: old.and_then(|o| o.note.clone()),
+                    ..new
+                }
+            })
+            .collect();
+        self
+    }
+
+    /// Index of the mod `query` points at, by uuid, workshop id or pack file name.
+    pub fn find_mod(&self, query: &str) -> Option<usize> {
+        self.active_mods.iter().position(|m| {
+            m.uuid.0 == query
+                || m.workshop_id.as_deref() == Some(query)
+                || m.packfile
+                    .as_deref()
+                    .map(|p| p.eq_ignore_ascii_case(query))
+                    .unwrap_or(false)
+        })
+    }
+
+    /// Reads a profile of any version, upgrading it to `PROFILE_VERSION`.
+    pub fn from_toml(data: &str) -> Result<Self> {
+        let mut table: toml::Table = toml::from_str(data)?;
+        let version = match table.get("version") {
+            None => first_profile_version(),
+            Some(v) => v
+                .as_integer()
+                .and_then(|v| u32::try_from(v).ok())
+                .ok_or_else(|| anyhow!("Profile version must be a number"))?,
+        };
+        if version > PROFILE_VERSION {
+            bail!("Profile is version {version}, but this jankloada only knows up to {PROFILE_VERSION}; time to update");
+        }
+        if version < 2 {
+            migrate_v1(&mut table);
+        }
+        table.insert("version".to_string(), PROFILE_VERSION.into());
+        Ok(toml::Value::Table(table).try_into()?)
+    }
+}
+
+/// Turns bare uuids in `active_mods` into tables.
+fn migrate_v1(table: &mut toml::Table) {
+    if let Some(toml::Value::Array(mods)) = table.get_mut("active_mods") {
+        for m in mods.iter_mut() {
+            if let toml::Value::String(uuid) = m {
+                let mut entry = toml::Table::new();
+                entry.insert("uuid".to_string(), uuid.clone().into());
+                *m = toml::Value::Table(entry);
+            }
+        }
+    }
+}
+
+/// A mod in a profile, with enough to recognise it on someone else's machine,
+/// where the launcher may have given it another uuid.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ProfileMod {
+    pub uuid: ModUUID,
+    /// File name of the pack, without its directory.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub packfile: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workshop_id: Option<String>,
+    /// Whatever the profile's author wanted to say about this mod.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+impl ProfileMod {
+    pub fn new(uuid: &str) -> Self {
+        Self {
+            uuid: ModUUID(uuid.to_string()),
+            packfile: None,
+            workshop_id: None,
+            note: None,
+        }
+    }
+}
+
+impl From<&ModEntry> for ProfileMod {
+    fn from(entry: &ModEntry) -> Self {
+        Self {
+            uuid: entry.uuid.clone(),
+            packfile: Some(entry.packfile_name().to_string()),
+            workshop_id: entry.workshop_id().map(str::to_string),
+            note: None,
+        }
+    }
+}
+
+impl fmt::Display for ProfileMod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.packfile {
+            Some(packfile) => write!(f, "{packfile} ({})", self.uuid.0),
+            None => write!(f, "{}", self.uuid.0),
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::mod_data::{ModEntry, ModList, ModProfile, ModUUID};
+    use std::path::Path;
+
+    use crate::mod_data::{
+        ApplyReport, MatchRule, ModEntry, ModFileDTO, ModList, ModListError, ModProfile, ModUUID,
+        ProfileMatch, ProfileMod, PROFILE_VERSION,
+    };
+
+    /// A made-up mod file in the launcher's compact format, with fields we
+    /// don't know about mixed in.
+    const LAUNCHER_FILE: &str = include_str!("../fixtures/20190104-moddata.dat");
+
+    /// The same sort of file as the launcher writes it when it pretty-prints,
+    /// with unknown fields nested and in odd places.
+    const PRETTY_LAUNCHER_FILE: &str = include_str!("../fixtures/20230611-moddata-pretty.dat");
+
+    fn round_trip(mod_list: ModList) -> String {
+        serde_json::to_string(&ModFileDTO::from(mod_list)).unwrap()
+    }
+
+    #[test]
+    fn launcher_file_round_trips_byte_for_byte() {
+        let mod_list: ModList = serde_json::from_str::<ModFileDTO>(LAUNCHER_FILE)
+            .unwrap()
+            .into();
+        assert_eq!(LAUNCHER_FILE, round_trip(mod_list));
+    }
+
+    #[test]
+    fn pretty_launcher_file_round_trips() {
+        let mod_list: ModList = serde_json::from_str::<ModFileDTO>(PRETTY_LAUNCHER_FILE)
+            .unwrap()
+            .into();
+        assert_eq!(3, mod_list.mods().len());
+        let fields = &mod_list.mods()[1].launcher_fields;
+        assert_eq!(
+            vec!["tags", "version"],
+            fields.iter().map(|(k, _)| k.as_str()).collect::<Vec<_>>()
+        );
+
+        let written = serde_json::to_string_pretty(&ModFileDTO::from(mod_list.clone())).unwrap();
+        assert_eq!(PRETTY_LAUNCHER_FILE, written);
+        let reread: ModList = serde_json::from_str::<ModFileDTO>(&written).unwrap().into();
+        assert_eq!(mod_list, reread);
+    }
+
+    #[test]
+    fn only_changed_fields_differ_after_round_trip() {
+        let mut mod_list: ModList = serde_json::from_str::<ModFileDTO>(LAUNCHER_FILE)
+            .unwrap()
+            .into();
+        mod_list.set_mod_active_state(1, true).unwrap();
+
+        let expected = LAUNCHER_FILE.replacen(
+            r#""active":false,"category":"mod","game":"warhammer3","name":"Mork's"#,
+            r#""active":true,"category":"mod","game":"warhammer3","name":"Mork's"#,
+            1,
+        );
+        assert_ne!(LAUNCHER_FILE, expected);
+        assert_eq!(expected, round_trip(mod_list));
+    }
+
+    #[test]
+    fn unknown_fields_keep_their_spot() {
+        let file = r#"[{"uuid":"a","tweaks":null,"name":"A","active":true,"zz":1,"category":"mod","game":"warhammer3","order":1,"owned":true,"packfile":"Z:/a.pack","short":"a","aa":{"b":2}}]"#;
+        let mut mod_list: ModList = serde_json::from_str::<ModFileDTO>(file).unwrap().into();
+        let fields = &mod_list.mods()[0].launcher_fields;
+        assert_eq!(
+            vec!["tweaks", "zz", "aa"],
+            fields.iter().map(|(k, _)| k.as_str()).collect::<Vec<_>>()
+        );
+        assert_eq!(Some(&serde_json::Value::Null), fields.get("tweaks"));
+        assert_eq!(None, fields.get("name"));
+
+        // Survives a trip through the history too
+        let snapshot = serde_json::to_string(&mod_list).unwrap();
+        mod_list = serde_json::from_str(&snapshot).unwrap();
+        assert_eq!(file, round_trip(mod_list));
+    }
 
     #[test]
     fn applying_profile_works() {
@@ -191,6 +865,7 @@ mod tests {
                 owned: true,
                 packfile: "/foo.pack".to_string(),
                 short: "the foo mod".to_string(),
+                launcher_fields: Default::default(),
             },
             ModEntry {
                 uuid: ModUUID("two".to_string()),
@@ -201,17 +876,454 @@ mod tests {
                 owned: true,
                 packfile: "/foo.pack".to_string(),
                 short: "the foo mod".to_string(),
+                launcher_fields: Default::default(),
             },
         ]);
-        let mod_profile = ModProfile {
-            name: "some_profile".to_string(),
-            active_mods: vec![ModUUID("one".to_string())],
-        };
-        mod_list.apply_profile(mod_profile);
+        let mod_profile = ModProfile::new("some_profile".to_string(), vec![ProfileMod::new("one")]);
+        let report = mod_list.apply_profile(mod_profile, MatchRule::Uuid);
 
         assert!(mod_list.0[0].active);
         assert_eq!("One".to_string(), mod_list.0[0].name);
         assert!(!mod_list.0[1].active);
-        assert_eq!(2, mod_list.0.len())
+        assert_eq!(2, mod_list.0.len());
+        assert_eq!(
+            ApplyReport {
+                matched: vec![ProfileMatch {
+                    wanted: ProfileMod::new("one"),
+                    uuid: ModUUID("one".to_string()),
+                    rule: MatchRule::Uuid,
+                }],
+                deactivated: vec![mod_list.0[1].clone()],
+                ..Default::default()
+            },
+            report
+        );
+    }
+
+    #[test]
+    fn apply_report_lists_what_did_not_fit() {
+        let mut mod_list = launcher_mod_list();
+        // The launcher sometimes lists a mod twice
+        let mut copy = mod_list.0[1].clone();
+        copy.name = "Mork's Spanners (copy)".to_string();
+        mod_list.0.push(copy);
+        let mod_profile = ModProfile::new(
+            "friend".to_string(),
+            vec![ProfileMod::new("gretchin"), ProfileMod::new("spanners")],
+        );
+
+        let report = mod_list.apply_profile(mod_profile, MatchRule::Packfile);
+        assert_eq!(vec![ProfileMod::new("gretchin")], report.not_found);
+        assert_eq!(
+            vec!["Ürk Boyz Overhaul", "Old World Stuff"],
+            report
+                .deactivated
+                .iter()
+                .map(|m| m.name.as_str())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(1, report.unordered.len());
+        assert_eq!("Mork's Spanners (copy)", report.unordered[0].name);
+        assert_eq!(
+            vec!["spanners", "spanners", "2789857593", "1234567"],
+            uuids(&mod_list)
+        );
+    }
+
+    fn launcher_mod_list() -> ModList {
+        serde_json::from_str::<ModFileDTO>(LAUNCHER_FILE)
+            .unwrap()
+            .into()
+    }
+
+    #[test]
+    fn finds_mods_exactly_then_fuzzily() {
+        let mod_list = launcher_mod_list();
+        let find = |query| mod_list.find_mod(query, "warhammer3");
+        assert_eq!(0, find("2789857593").unwrap());
+        assert_eq!(1, find("Mork's Spanners").unwrap());
+        assert_eq!(1, find("spanners.pack").unwrap());
+        assert_eq!(0, find("ürkboyz").unwrap());
+        assert_eq!(0, find("URK").unwrap());
+        // "spanners" is Mork's uuid, which wins over fuzzy matches
+        assert_eq!(1, find("spanners").unwrap());
+        assert!(matches!(find("o"), Err(ModListError::Ambiguous { .. })));
+
+        // However the launcher spelled the path
+        let mut windows = mod_list.clone();
+        windows.0[1].packfile = r"C:\Games\WARHAMMER III\data\spanners.pack".to_string();
+        assert_eq!(Ok(1), windows.find_mod("spanners.pack", "warhammer3"));
+        assert_eq!(
+            Err(ModListError::NoMatch("gretchin".to_string())),
+            find("gretchin")
+        );
+    }
+
+    #[test]
+    fn only_finds_mods_for_the_game() {
+        let mod_list = launcher_mod_list();
+        // Old World Stuff is for warhammer2
+        for query in ["Z:/home/gork/old.pack", "old world", "oldwrld"] {
+            assert_eq!(
+                Err(ModListError::NoMatch(query.to_string())),
+                mod_list.find_mod(query, "warhammer3")
+            );
+            assert_eq!(Ok(2), mod_list.find_mod(query, "warhammer2"));
+        }
+    }
+
+    fn uuids(mod_list: &ModList) -> Vec<&str> {
+        mod_list.mods().iter().map(|m| m.uuid.0.as_str()).collect()
+    }
+
+    #[test]
+    fn moving_mods_keeps_the_rest_in_order() {
+        let urk = ModUUID("2789857593".to_string());
+        let spanners = ModUUID("spanners".to_string());
+        let old = ModUUID("1234567".to_string());
+        let mut mod_list = launcher_mod_list();
+
+        mod_list.move_mod(0, 2).unwrap();
+        assert_eq!(vec!["spanners", "1234567", "2789857593"], uuids(&mod_list));
+        mod_list.move_before(&urk, &spanners).unwrap();
+        assert_eq!(vec!["2789857593", "spanners", "1234567"], uuids(&mod_list));
+        mod_list.move_after(&urk, &old).unwrap();
+        assert_eq!(vec!["spanners", "1234567", "2789857593"], uuids(&mod_list));
+        mod_list.move_after(&old, &spanners).unwrap();
+        assert_eq!(vec!["spanners", "1234567", "2789857593"], uuids(&mod_list));
+        mod_list.swap(0, 2).unwrap();
+        assert_eq!(vec!["2789857593", "1234567", "spanners"], uuids(&mod_list));
+        mod_list.sort_by_key(|m| m.name.clone());
+        assert_eq!(vec!["spanners", "1234567", "2789857593"], uuids(&mod_list));
+    }
+
+    #[test]
+    fn bad_moves_leave_the_list_alone() {
+        let urk = ModUUID("2789857593".to_string());
+        let mut mod_list = launcher_mod_list();
+        let before = mod_list.clone();
+
+        assert_eq!(
+            Err(ModListError::IndexOutOfRange { index: 3, len: 3 }),
+            mod_list.move_mod(0, 3)
+        );
+        assert_eq!(
+            Err(ModListError::IndexOutOfRange { index: 5, len: 3 }),
+            mod_list.swap(5, 0)
+        );
+        assert_eq!(
+            Err(ModListError::UnknownUuid(ModUUID("gretchin".to_string()))),
+            mod_list.move_before(&urk, &ModUUID("gretchin".to_string()))
+        );
+        assert_eq!(Err(ModListError::SameMod), mod_list.move_after(&urk, &urk));
+        assert_eq!(
+            Err(ModListError::IndexOutOfRange { index: 9, len: 3 }),
+            mod_list.set_mod_active_state(9, true)
+        );
+        assert_eq!(before, mod_list);
+    }
+
+    #[test]
+    fn profile_mods_match_across_machines() {
+        let mut mod_list = launcher_mod_list();
+        // Friend has Ürk Boyz from the workshop under another uuid, and a
+        // local copy of the spanners
+        let friend = ModProfile::new(
+            "friend".to_string(),
+            vec![
+                ProfileMod {
+                    packfile: Some("renamed.pack".to_string()),
+                    workshop_id: Some("2789857593".to_string()),
+                    ..ProfileMod::new("friends-urk")
+                },
+                ProfileMod {
+                    packfile: Some("Spanners.pack".to_string()),
+                    ..ProfileMod::new("friends-spanners")
+                },
+            ],
+        );
+
+        let strict = mod_list
+            .clone()
+            .apply_profile(friend.clone(), MatchRule::Uuid);
+        assert!(strict.matched.is_empty());
+        assert_eq!(2, strict.not_found.len());
+
+        let report = mod_list.apply_profile(friend, MatchRule::Packfile);
+        assert!(report.not_found.is_empty());
+        assert_eq!(
+            vec![
+                ("2789857593", MatchRule::WorkshopId),
+                ("spanners", MatchRule::Packfile)
+            ],
+            report
+                .matched
+                .iter()
+                .map(|m| (m.uuid.0.as_str(), m.rule))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(vec!["2789857593", "spanners", "1234567"], uuids(&mod_list));
+        assert_eq!(2, mod_list.get_active().len());
+    }
+
+    #[test]
+    fn installed_mods_are_only_matched_once() {
+        let mut mod_list = launcher_mod_list();
+        // Both ask for spanners.pack, but only the second one by its uuid
+        let profile = ModProfile::new(
+            "greedy".to_string(),
+            vec![
+                ProfileMod {
+                    packfile: Some("spanners.pack".to_string()),
+                    ..ProfileMod::new("other-spanners")
+                },
+                ProfileMod::new("spanners"),
+                ProfileMod {
+                    packfile: Some("spanners.pack".to_string()),
+                    ..ProfileMod::new("more-spanners")
+                },
+            ],
+        );
+
+        let report = mod_list.apply_profile(profile, MatchRule::Packfile);
+        assert_eq!(
+            vec![("spanners", MatchRule::Uuid)],
+            report
+                .matched
+                .iter()
+                .map(|m| (m.uuid.0.as_str(), m.rule))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec!["other-spanners", "more-spanners"],
+            report
+                .not_found
+                .iter()
+                .map(|m| m.uuid.0.as_str())
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn profiles_for_one_game_leave_the_others_alone() {
+        let mut mod_list = launcher_mod_list();
+        // A warhammer2 mod with the same pack name, ahead of the warhammer3 one
+        let twin = ModEntry {
+            uuid: ModUUID("old-spanners".to_string()),
+            game: "warhammer2".to_string(),
+            active: true,
+            ..mod_list.0[1].clone()
+        };
+        mod_list.0.insert(0, twin);
+        let profile = ModProfile {
+            game: Some("warhammer3".to_string()),
+            ..ModProfile::new(
+                "spanners only".to_string(),
+                vec![ProfileMod {
+                    packfile: Some("spanners.pack".to_string()),
+                    ..ProfileMod::new("other-spanners")
+                }],
+            )
+        };
+
+        let report = mod_list.apply_profile(profile, MatchRule::Packfile);
+        assert_eq!("spanners", report.matched[0].uuid.0);
+        assert_eq!(1, report.deactivated.len());
+        assert_eq!("Ürk Boyz Overhaul", report.deactivated[0].name);
+        let active: Vec<_> = mod_list
+            .get_active()
+            .iter()
+            .map(|m| m.uuid.0.as_str())
+            .collect();
+        assert_eq!(vec!["spanners", "old-spanners", "1234567"], active);
+    }
+
+    #[test]
+    fn profiles_with_bare_uuids_still_load() {
+        let profile = ModProfile::from_toml(
+            r#"
+            name = "old"
+            active_mods = ["2789857593", { uuid = "spanners", packfile = "spanners.pack" }]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            vec![
+                ProfileMod::new("2789857593"),
+                ProfileMod {
+                    packfile: Some("spanners.pack".to_string()),
+                    ..ProfileMod::new("spanners")
+                }
+            ],
+            profile.active_mods
+        );
+
+        let saved =
+            ModProfile::new_from_mod_list("new".to_string(), &launcher_mod_list(), "warhammer3");
+        assert_eq!(
+            ProfileMod {
+                uuid: ModUUID("2789857593".to_string()),
+                packfile: Some("urk_boyz.pack".to_string()),
+                workshop_id: Some("2789857593".to_string()),
+                note: None,
+            },
+            saved.active_mods[0]
+        );
+        let reloaded = ModProfile::from_toml(&toml::to_string(&saved).unwrap()).unwrap();
+        assert_eq!(saved.active_mods, reloaded.active_mods);
+    }
+
+    #[test]
+    fn profiles_keep_metadata_and_notes() {
+        let old = ModProfile::from_toml(
+            r#"name = "old"
+active_mods = ["spanners"]"#,
+        )
+        .unwrap();
+        assert_eq!(PROFILE_VERSION, old.version);
+        assert!(old.description.is_none() && old.tags.is_empty());
+
+        let mut profile = ModProfile {
+            description: Some("Proppa Orky".to_string()),
+            tags: vec!["orks".to_string()],
+            ..old
+        };
+        profile.active_mods[0].note = Some("needs more dakka".to_string());
+        let mut mod_list = launcher_mod_list();
+        mod_list.set_mod_active_state(1, true).unwrap();
+        let resaved = profile.with_mods_from(&mod_list, "warhammer3");
+        assert_eq!(Some("Proppa Orky"), resaved.description.as_deref());
+        assert_eq!(Some("warhammer3"), resaved.game.as_deref());
+        // Old World Stuff is active too, but for warhammer2
+        assert_eq!(2, resaved.active_mods.len());
+        assert_eq!(None, resaved.find_mod("1234567"));
+        let spanners = resaved.find_mod("spanners.pack").unwrap();
+        assert_eq!(
+            Some("needs more dakka"),
+            resaved.active_mods[spanners].note.as_deref()
+        );
+        assert_eq!(Some(0), resaved.find_mod("2789857593"));
+
+        let reloaded = ModProfile::from_toml(&toml::to_string(&resaved).unwrap()).unwrap();
+        assert_eq!(resaved, reloaded);
+
+        let future = format!(
+            "version = {}\nname = \"x\"\nactive_mods = []",
+            PROFILE_VERSION + 1
+        );
+        assert!(ModProfile::from_toml(&future).is_err());
+
+        // Without from_toml, there's nothing to upgrade it, but it still reads
+        let unversioned: ModProfile = toml::from_str(
+            r#"name = "old"
+active_mods = [{ uuid = "spanners" }]"#,
+        )
+        .unwrap();
+        assert_eq!(1, unversioned.version);
+    }
+
+    #[test]
+    fn foreign_mod_files_become_profiles() {
+        let mut friends = launcher_mod_list();
+        friends.set_mod_active_state(1, true).unwrap();
+        friends.move_mod(1, 0).unwrap();
+        let profile = ModProfile::new_from_mod_list("friend".to_string(), &friends, "warhammer3");
+        assert_eq!(Some("warhammer3"), profile.game.as_deref());
+        // Old World Stuff is active too, but for another game
+        assert_eq!(
+            vec!["spanners", "2789857593"],
+            profile
+                .active_mods
+                .iter()
+                .map(|m| m.uuid.0.as_str())
+                .collect::<Vec<_>>()
+        );
+
+        let mut mine = launcher_mod_list();
+        mine.0.remove(0);
+        let report = mine.apply_profile(profile, MatchRule::Packfile);
+        assert_eq!(1, report.not_found.len());
+        assert_eq!(
+            Some("urk_boyz.pack"),
+            report.not_found[0].packfile.as_deref()
+        );
+    }
+
+    #[test]
+    fn finds_preview_images_next_to_packs() {
+        let dir = tempfile::tempdir().unwrap();
+        let workshop = dir.path().join("workshop/content/1142710/2789857593");
+        let data = dir.path().join("data");
+        std::fs::create_dir_all(&workshop).unwrap();
+        std::fs::create_dir_all(&data).unwrap();
+        for file in [
+            workshop.join("urk_boyz.pack"),
+            workshop.join("Preview.PNG"),
+            data.join("spanners.pack"),
+            data.join("spanners.png"),
+            data.join("grots.pack"),
+            data.join("other.png"),
+        ] {
+            std::fs::write(file, "").unwrap();
+        }
+        let entry = |packfile: &Path| ModEntry {
+            // Like Proton writes them
+            packfile: format!("Z:{}", packfile.display()),
+            ..launcher_mod_list().0[0].clone()
+        };
+
+        assert_eq!(
+            Some(workshop.join("Preview.PNG")),
+            entry(&workshop.join("urk_boyz.pack")).preview_image()
+        );
+        assert_eq!(
+            Some(data.join("spanners.png")),
+            entry(&data.join("spanners.pack")).preview_image()
+        );
+        assert_eq!(None, entry(&data.join("grots.pack")).preview_image());
+    }
+
+    #[test]
+    fn pruning_removes_only_missing_mods() {
+        let dir = tempfile::tempdir().unwrap();
+        let pack = dir.path().join("here.pack");
+        std::fs::write(&pack, "").unwrap();
+        let entry = |uuid: &str, packfile: String| ModEntry {
+            uuid: ModUUID(uuid.to_string()),
+            name: uuid.to_string(),
+            active: true,
+            category: "mod".to_string(),
+            game: "warhammer3".to_string(),
+            owned: true,
+            packfile,
+            short: "".to_string(),
+            launcher_fields: Default::default(),
+        };
+        let mut mod_list = ModList(vec![
+            entry("gone", "/no/such/gone.pack".to_string()),
+            entry("here", pack.display().to_string()),
+        ]);
+
+        let report = mod_list.prune_missing();
+        assert_eq!(
+            vec!["gone"],
+            report
+                .removed
+                .iter()
+                .map(|m| m.name.as_str())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec!["here"],
+            mod_list
+                .mods()
+                .iter()
+                .map(|m| m.name.as_str())
+                .collect::<Vec<_>>()
+        );
+
+        assert!(mod_list.remove_mod(1).is_err());
+        assert_eq!("here", mod_list.remove_mod(0).unwrap().name);
+        assert!(mod_list.mods().is_empty());
     }
 }
@@ -0,0 +1,417 @@
+//! Read-only access to the index of Warhammer 3 `.pack` files (PFH5).
+//!
+//! A pack starts with a header, followed by the names of the packs it
+//! depends on, then one entry per file, then the files' data back to back
+//! in index order. Only the header and the indexes are read.
+
+use std::{
+    fmt,
+    fs::File,
+    io::{BufReader, Read},
+    path::Path,
+};
+
+use anyhow::{bail, Context, Result};
+
+const MAGIC: &[u8; 4] = b"PFH5";
+/// Magic, type and flags, four index counts/sizes and a timestamp.
+const HEADER_SIZE: u64 = 28;
+/// Extra header bytes when `PackFlags::BIG_HEADER` is set.
+const BIG_HEADER_EXTRA: u64 = 20;
+/// More files than any real pack has; beyond this the header is garbage.
+const MAX_FILES: u32 = 10_000_000;
+
+/// What the game uses a pack for, from the low bits of its type field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PackType {
+    Boot,
+    Release,
+    Patch,
+    Mod,
+    Movie,
+    Unknown(u32),
+}
+
+impl From<u32> for PackType {
+    fn from(value: u32) -> Self {
+        match value {
+            0 => PackType::Boot,
+            1 => PackType::Release,
+            2 => PackType::Patch,
+            3 => PackType::Mod,
+            4 => PackType::Movie,
+            other => PackType::Unknown(other),
+        }
+    }
+}
+
+impl fmt::Display for PackType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PackType::Boot => write!(f, "boot"),
+            PackType::Release => write!(f, "release"),
+            PackType::Patch => write!(f, "patch"),
+            PackType::Mod => write!(f, "mod"),
+            PackType::Movie => write!(f, "movie"),
+            PackType::Unknown(n) => write!(f, "unknown ({n})"),
+        }
+    }
+}
+
+/// The high bits of the type field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PackFlags(pub u32);
+
+impl PackFlags {
+    pub const ENCRYPTED_DATA: u32 = 0x10;
+    pub const INDEX_WITH_TIMESTAMPS: u32 = 0x40;
+    pub const ENCRYPTED_INDEX: u32 = 0x80;
+    pub const BIG_HEADER: u32 = 0x100;
+
+    pub fn contains(&self, flag: u32) -> bool {
+        self.0 & flag != 0
+    }
+}
+
+/// A file inside a pack.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackEntry {
+    /// Path inside the pack, with `/` separators, e.g. `db/units_tables/my_units`.
+    pub path: String,
+    /// Size in the pack, so compressed if `compressed`.
+    pub size: u32,
+    /// Where the data starts, from the beginning of the pack.
+    pub offset: u64,
+    /// Only there if the pack has `PackFlags::INDEX_WITH_TIMESTAMPS`.
+    pub timestamp: Option<u32>,
+    pub compressed: bool,
+}
+
+/// The header and indexes of a pack.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackIndex {
+    pub pack_type: PackType,
+    pub flags: PackFlags,
+    /// Seconds since the epoch, as written by whatever made the pack.
+    pub timestamp: u32,
+    /// Names of the packs this one needs.
+    pub dependencies: Vec<String>,
+    pub files: Vec<PackEntry>,
+}
+
+impl PackIndex {
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let file = File::open(path).with_context(|| format!("Could not open pack {path:?}"))?;
+        Self::read(BufReader::new(file)).with_context(|| format!("Could not read pack {path:?}"))
+    }
+
+    pub fn read(mut reader: impl Read) -> Result<Self> {
+        let mut magic = [0; 4];
+        reader
+            .read_exact(&mut magic)
+            .context("File is too short to be a pack")?;
+        if &magic != MAGIC {
+            bail!(
+                "Not a Warhammer 3 pack, it starts with {:?} instead of PFH5",
+                String::from_utf8_lossy(&magic)
+            );
+        }
+        let type_and_flags = read_u32(&mut reader)?;
+        let dependency_count = read_u32(&mut reader)?;
+        let dependency_index_size = read_u32(&mut reader)?;
+        let file_count = read_u32(&mut reader)?;
+        let file_index_size = read_u32(&mut reader)?;
+        let timestamp = read_u32(&mut reader)?;
+
+        let flags = PackFlags(type_and_flags & !0xf);
+        if flags.contains(PackFlags::ENCRYPTED_INDEX) {
+            bail!("Pack has an encrypted index, which isn't supported");
+        }
+        if file_count > MAX_FILES {
+            bail!("Pack claims to have {file_count} files, it's probably damaged");
+        }
+        let mut header_size = HEADER_SIZE;
+        if flags.contains(PackFlags::BIG_HEADER) {
+            skip(&mut reader, BIG_HEADER_EXTRA)?;
+            header_size += BIG_HEADER_EXTRA;
+        }
+
+        let dependency_index = read_bytes(&mut reader, dependency_index_size)?;
+        let mut dependencies = Vec::new();
+        let mut cursor = dependency_index.as_slice();
+        for _ in 0..dependency_count {
+            dependencies.push(read_string(&mut cursor)?);
+        }
+
+        let file_index = read_bytes(&mut reader, file_index_size)?;
+        let mut cursor = file_index.as_slice();
+        let mut offset =
+            header_size + u64::from(dependency_index_size) + u64::from(file_index_size);
+        // Every entry takes at least 6 bytes, so a bogus count can't allocate much
+        let mut files = Vec::with_capacity(file_count.min(file_index_size / 6) as usize);
+        for _ in 0..file_count {
+            let size = read_u32(&mut cursor)?;
+            let timestamp = if flags.contains(PackFlags::INDEX_WITH_TIMESTAMPS) {
+                Some(read_u32(&mut cursor)?)
+            } else {
+                None
+            };
+            let compressed = read_u8(&mut cursor)? != 0;
+            let path = read_string(&mut cursor)?.replace('\\', "/");
+            files.push(PackEntry {
+                path,
+                size,
+                offset,
+                timestamp,
+                compressed,
+            });
+            offset += u64::from(size);
+        }
+
+        Ok(Self {
+            pack_type: PackType::from(type_and_flags & 0xf),
+            flags,
+            timestamp,
+            dependencies,
+            files,
+        })
+    }
+
+    /// Total size of the files' data.
+    pub fn data_size(&self) -> u64 {
+        self.files.iter().map(|f| u64::from(f.size)).sum()
+    }
+}
+
+/// One line of a pack's contents drawn as a tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreeLine<'a> {
+    /// How many folders deep it is.
+    pub depth: usize,
+    /// The last part of the path.
+    pub name: &'a str,
+    /// `None` for folders.
+    pub entry: Option<&'a PackEntry>,
+}
+
+/// `files` as a tree: sorted, with a line for each folder before what's in it.
+pub fn file_tree<'a>(files: impl IntoIterator<Item = &'a PackEntry>) -> Vec<TreeLine<'a>> {
+    let mut files: Vec<_> = files.into_iter().collect();
+    files.sort_by_cached_key(|f| f.path.to_lowercase());
+    let mut lines = Vec::new();
+    let mut open: Vec<&str> = Vec::new();
+    for entry in files {
+        let mut parts: Vec<_> = entry.path.split('/').collect();
+        let name = parts.pop().unwrap_or_default();
+        let same = open
+            .iter()
+            .zip(&parts)
+            .take_while(|(a, b)| a.eq_ignore_ascii_case(b))
+            .count();
+        open.truncate(same);
+        for folder in &parts[same..] {
+            lines.push(TreeLine {
+                depth: open.len(),
+                name: folder,
+                entry: None,
+            });
+            open.push(folder);
+        }
+        lines.push(TreeLine {
+            depth: open.len(),
+            name,
+            entry: Some(entry),
+        });
+    }
+    lines
+}
+
+fn read_u8(reader: &mut impl Read) -> Result<u8> {
+    let mut buf = [0; 1];
+    reader
+        .read_exact(&mut buf)
+        .context("Pack index ends too soon")?;
+    Ok(buf[0])
+}
+
+fn read_u32(reader: &mut impl Read) -> Result<u32> {
+    let mut buf = [0; 4];
+    reader
+        .read_exact(&mut buf)
+        .context("Pack index ends too soon")?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_bytes(reader: &mut impl Read, len: u32) -> Result<Vec<u8>> {
+    let mut buf = Vec::new();
+    reader.take(u64::from(len)).read_to_end(&mut buf)?;
+    if buf.len() != len as usize {
+        bail!("Pack index ends too soon");
+    }
+    Ok(buf)
+}
+
+fn skip(reader: &mut impl Read, len: u64) -> Result<()> {
+    let skipped = std::io::copy(&mut reader.take(len), &mut std::io::sink())?;
+    if skipped != len {
+        bail!("Pack header ends too soon");
+    }
+    Ok(())
+}
+
+/// A NUL-terminated string. Packs are mostly ASCII, anything else is replaced.
+fn read_string(cursor: &mut &[u8]) -> Result<String> {
+    let Some(end) = cursor.iter().position(|&b| b == 0) else {
+        bail!("Pack index ends in the middle of a name");
+    };
+    let s = String::from_utf8_lossy(&cursor[..end]).into_owned();
+    *cursor = &cursor[end + 1..];
+    Ok(s)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::{file_tree, PackFlags, PackIndex, PackType};
+
+    /// Builds a PFH5 mod pack with `files` (path and contents), flagged with `flags`.
+    pub(crate) fn build_pack(
+        flags: u32,
+        dependencies: &[&str],
+        files: &[(&str, &[u8])],
+    ) -> Vec<u8> {
+        let mut dependency_index = Vec::new();
+        for d in dependencies {
+            dependency_index.extend_from_slice(d.as_bytes());
+            dependency_index.push(0);
+        }
+        let mut file_index = Vec::new();
+        for (path, data) in files {
+            file_index.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            if flags & PackFlags::INDEX_WITH_TIMESTAMPS != 0 {
+                file_index.extend_from_slice(&1_700_000_000u32.to_le_bytes());
+            }
+            file_index.push(0);
+            file_index.extend_from_slice(path.as_bytes());
+            file_index.push(0);
+        }
+
+        let mut pack = b"PFH5".to_vec();
+        for n in [
+            3 | flags,
+            dependencies.len() as u32,
+            dependency_index.len() as u32,
+            files.len() as u32,
+            file_index.len() as u32,
+            1_600_000_000,
+        ] {
+            pack.extend_from_slice(&n.to_le_bytes());
+        }
+        if flags & PackFlags::BIG_HEADER != 0 {
+            pack.extend_from_slice(&[0xee; 20]);
+        }
+        pack.extend(dependency_index);
+        pack.extend(file_index);
+        for (_, data) in files {
+            pack.extend_from_slice(data);
+        }
+        pack
+    }
+
+    #[test]
+    fn reads_the_index() {
+        let pack = build_pack(
+            0,
+            &["data.pack"],
+            &[
+                ("db\\units_tables\\urk_boyz", b"boyz"),
+                ("script/campaign/mod/waaagh.lua", b"-- more dakka"),
+            ],
+        );
+        let index = PackIndex::read(pack.as_slice()).unwrap();
+        assert_eq!(PackType::Mod, index.pack_type);
+        assert_eq!(PackFlags(0), index.flags);
+        assert_eq!(1_600_000_000, index.timestamp);
+        assert_eq!(vec!["data.pack"], index.dependencies);
+        assert_eq!(
+            vec!["db/units_tables/urk_boyz", "script/campaign/mod/waaagh.lua"],
+            index
+                .files
+                .iter()
+                .map(|f| f.path.as_str())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(17, index.data_size());
+
+        // Offsets point at each file's data
+        for (entry, data) in index.files.iter().zip([&b"boyz"[..], b"-- more dakka"]) {
+            let start = entry.offset as usize;
+            assert_eq!(data, &pack[start..start + entry.size as usize]);
+            assert_eq!(None, entry.timestamp);
+            assert!(!entry.compressed);
+        }
+    }
+
+    #[test]
+    fn reads_big_headers_and_timestamps() {
+        let flags = PackFlags::BIG_HEADER | PackFlags::INDEX_WITH_TIMESTAMPS;
+        let pack = build_pack(flags, &[], &[("text/db/urk.loc", b"loc")]);
+        let index = PackIndex::read(pack.as_slice()).unwrap();
+        assert!(index.flags.contains(PackFlags::BIG_HEADER));
+        let entry = &index.files[0];
+        assert_eq!(Some(1_700_000_000), entry.timestamp);
+        assert_eq!(b"loc", &pack[entry.offset as usize..]);
+    }
+
+    #[test]
+    fn draws_a_file_tree() {
+        let pack = build_pack(
+            0,
+            &[],
+            &[
+                ("script/waaagh.lua", b""),
+                ("db/units_tables/boyz", b""),
+                ("DB/spanners", b""),
+                ("db/units_tables/grots", b""),
+                ("readme.txt", b""),
+            ],
+        );
+        let index = PackIndex::read(pack.as_slice()).unwrap();
+        let tree: Vec<_> = file_tree(&index.files)
+            .into_iter()
+            .map(|l| {
+                format!(
+                    "{}{}{}",
+                    "  ".repeat(l.depth),
+                    l.name,
+                    if l.entry.is_some() { "" } else { "/" }
+                )
+            })
+            .collect();
+        assert_eq!(
+            vec![
+                "DB/",
+                "  spanners",
+                "  units_tables/",
+                "    boyz",
+                "    grots",
+                "readme.txt",
+                "script/",
+                "  waaagh.lua",
+            ],
+            tree
+        );
+    }
+
+    #[test]
+    fn refuses_what_it_cannot_read() {
+        let pack = build_pack(0, &[], &[("db/a", b"a"), ("db/b", b"b")]);
+        // Cut off in the middle of the file index
+        assert!(PackIndex::read(&pack[..pack.len() - 8]).is_err());
+        assert!(PackIndex::read(&b"PFH4"[..]).is_err());
+        assert!(PackIndex::read(&b""[..]).is_err());
+        let encrypted = build_pack(PackFlags::ENCRYPTED_INDEX, &[], &[("db/a", b"a")]);
+        assert!(PackIndex::read(encrypted.as_slice()).is_err());
+        // Only the index is read, so missing file data is fine
+        assert!(PackIndex::read(&pack[..pack.len() - 2]).is_ok());
+    }
+}
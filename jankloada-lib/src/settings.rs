@@ -0,0 +1,49 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::mod_data::MatchRule;
+
+pub(crate) const SETTINGS_FILE: &str = "settings.toml";
+/// The launcher's name for Warhammer 3, used when no game is set.
+pub const DEFAULT_GAME: &str = "warhammer3";
+const DEFAULT_BACKUP_COUNT: usize = 10;
+const DEFAULT_HISTORY_COUNT: usize = 100;
+
+/// Persisted app settings, kept in the jankloada data dir.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct Settings {
+    /// Mod file to use instead of the auto-detected one.
+    pub mod_file: Option<PathBuf>,
+    /// Where to keep profiles instead of next to this file.
+    pub data_dir: Option<PathBuf>,
+    /// Only mods for this game are shown, as the launcher's mod file is shared.
+    pub default_game: Option<String>,
+    /// Profile to use when none is given.
+    pub preferred_profile: Option<String>,
+    /// How many mod file backups to keep. 0 turns backups off.
+    pub backup_count: Option<usize>,
+    /// How many mod lists to keep in the undo history.
+    pub history_count: Option<usize>,
+    /// The loosest way a profile mod may be matched to an installed one.
+    pub match_strictness: Option<MatchRule>,
+}
+
+impl Settings {
+    pub fn game(&self) -> &str {
+        self.default_game.as_deref().unwrap_or(DEFAULT_GAME)
+    }
+
+    pub fn backup_count(&self) -> usize {
+        self.backup_count.unwrap_or(DEFAULT_BACKUP_COUNT)
+    }
+
+    pub fn history_count(&self) -> usize {
+        self.history_count.unwrap_or(DEFAULT_HISTORY_COUNT)
+    }
+
+    pub fn match_strictness(&self) -> MatchRule {
+        self.match_strictness.unwrap_or(MatchRule::Packfile)
+    }
+}
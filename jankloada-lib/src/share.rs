@@ -0,0 +1,175 @@
+//! Share codes: a profile squeezed into one line of text that survives being
+//! pasted into chat.
+//!
+//! A code is `jank<version>:` followed by URL-safe base64 of a CRC-32 of the
+//! payload (4 bytes, big endian) and the deflated payload. The payload is a
+//! JSON array of the profile name, its game, and its mods in load order as
+//! `[uuid, packfile, workshop id]`.
+
+use std::io::{Read, Write};
+
+use anyhow::{anyhow, bail, Context, Result};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use flate2::{read::DeflateDecoder, write::DeflateEncoder, Compression};
+use serde::{Deserialize, Serialize};
+
+use crate::mod_data::{ModProfile, ModUUID, ProfileMod};
+
+/// Version of the share code format, bumped whenever the payload changes.
+pub const SHARE_CODE_VERSION: u32 = 1;
+
+const PREFIX: &str = "jank";
+
+/// Way more than any real profile needs; a pasted code can't unpack to more.
+const MAX_PAYLOAD: u64 = 1024 * 1024;
+
+#[derive(Serialize, Deserialize)]
+struct Payload(String, Option<String>, Vec<SharedMod>);
+
+#[derive(Serialize, Deserialize)]
+struct SharedMod(ModUUID, Option<String>, Option<String>);
+
+impl ModProfile {
+    /// Encodes the profile's name, game and mods; notes and the rest of the
+    /// metadata stay at home.
+    pub fn to_share_code(&self) -> Result<String> {
+        let payload = Payload(
+            self.name.clone(),
+            self.game.clone(),
+            self.active_mods
+                .iter()
+                .map(|m| SharedMod(m.uuid.clone(), m.packfile.clone(), m.workshop_id.clone()))
+                .collect(),
+        );
+        let json = serde_json::to_vec(&payload)?;
+        let mut bytes = crc32fast::hash(&json).to_be_bytes().to_vec();
+        let mut encoder = DeflateEncoder::new(&mut bytes, Compression::best());
+        encoder.write_all(&json)?;
+        encoder.finish()?;
+        Ok(format!(
+            "{PREFIX}{SHARE_CODE_VERSION}:{}",
+            URL_SAFE_NO_PAD.encode(bytes)
+        ))
+    }
+
+    /// Decodes a code made by `to_share_code`. Whitespace and the backticks
+    /// chat apps like to add are ignored.
+    pub fn from_share_code(code: &str) -> Result<Self> {
+        let code: String = code
+            .chars()
+            .filter(|c| !c.is_whitespace() && *c != '`')
+            .collect();
+        let (version, data) = code
+            .strip_prefix(PREFIX)
+            .and_then(|rest| rest.split_once(':'))
+            .ok_or_else(|| anyhow!("That's not a jankloada share code"))?;
+        let version: u32 = version
+            .parse()
+            .map_err(|_| anyhow!("That's not a jankloada share code"))?;
+        if version > SHARE_CODE_VERSION {
+            bail!("Share code is version {version}, but this jankloada only knows up to {SHARE_CODE_VERSION}; time to update");
+        }
+
+        let bytes = URL_SAFE_NO_PAD
+            .decode(data)
+            .context("Share code is damaged, was all of it copied?")?;
+        if bytes.len() < 4 {
+            bail!("Share code is too short, was all of it copied?");
+        }
+        let (checksum, deflated) = bytes.split_at(4);
+        let mut json = Vec::new();
+        DeflateDecoder::new(deflated)
+            .take(MAX_PAYLOAD)
+            .read_to_end(&mut json)
+            .context("Share code is damaged, was all of it copied?")?;
+        if json.len() as u64 >= MAX_PAYLOAD {
+            bail!("Share code unpacks to way too much, that's no profile");
+        }
+        if crc32fast::hash(&json).to_be_bytes() != checksum {
+            bail!("Share code is damaged, its checksum doesn't match");
+        }
+
+        let Payload(name, game, mods) = serde_json::from_slice(&json)?;
+        Ok(ModProfile {
+            game,
+            ..ModProfile::new(
+                name,
+                mods.into_iter()
+                    .map(|SharedMod(uuid, packfile, workshop_id)| ProfileMod {
+                        uuid,
+                        packfile,
+                        workshop_id,
+                        note: None,
+                    })
+                    .collect(),
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+    use flate2::{write::DeflateEncoder, Compression};
+
+    use super::MAX_PAYLOAD;
+    use crate::mod_data::{ModFileDTO, ModList, ModProfile};
+
+    const LAUNCHER_FILE: &str = include_str!("../fixtures/20190104-moddata.dat");
+
+    fn profile() -> ModProfile {
+        let mod_list: ModList = serde_json::from_str::<ModFileDTO>(LAUNCHER_FILE)
+            .unwrap()
+            .into();
+        ModProfile {
+            description: Some("stays at home".to_string()),
+            ..ModProfile::new_from_mod_list("Ürk boyz".to_string(), &mod_list, "warhammer3")
+        }
+    }
+
+    #[test]
+    fn share_codes_round_trip() {
+        let profile = profile();
+        let code = profile.to_share_code().unwrap();
+        assert!(code.starts_with("jank1:"));
+
+        // Wrapped and quoted like a chat app would
+        let pasted = format!("`{}\n  {}`", &code[..20], &code[20..]);
+        let decoded = ModProfile::from_share_code(&pasted).unwrap();
+        assert_eq!(profile.name, decoded.name);
+        assert_eq!(profile.game, decoded.game);
+        assert_eq!(profile.active_mods, decoded.active_mods);
+        assert!(decoded.description.is_none());
+    }
+
+    #[test]
+    fn damaged_share_codes_are_refused() {
+        let code = profile().to_share_code().unwrap();
+        assert!(ModProfile::from_share_code(&code[..code.len() - 5]).is_err());
+        assert!(ModProfile::from_share_code("jank99:AAAA").is_err());
+        assert!(ModProfile::from_share_code("name = \"a toml file\"").is_err());
+
+        // Garble the deflated data, just past the checksum
+        let (head, data) = code.split_at(12);
+        let flipped = if data.starts_with('A') { 'B' } else { 'A' };
+        let damaged = format!("{head}{flipped}{}", &data[1..]);
+        assert!(ModProfile::from_share_code(&damaged).is_err());
+    }
+
+    #[test]
+    fn huge_share_codes_are_refused() {
+        // A few kilobytes that unpack to more than the limit
+        let json = vec![b' '; MAX_PAYLOAD as usize * 2];
+        let mut bytes = crc32fast::hash(&json).to_be_bytes().to_vec();
+        let mut encoder = DeflateEncoder::new(&mut bytes, Compression::best());
+        encoder.write_all(&json).unwrap();
+        encoder.finish().unwrap();
+        let code = format!("jank1:{}", URL_SAFE_NO_PAD.encode(bytes));
+        assert!(code.len() < 10_000);
+
+        let error = ModProfile::from_share_code(&code).unwrap_err();
+        assert!(error.to_string().contains("way too much"));
+    }
+}
@@ -0,0 +1,332 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, TimeZone, Utc};
+
+use crate::{
+    data_manager::{CA_LAUNCHER_DIR, CA_MOD_FILE},
+    mod_data::ModList,
+    vdf,
+};
+
+/// Steam app id of Total War: Warhammer 3.
+pub const TWWH3_APP_ID: &str = "1142710";
+
+/// A Steam library folder, as listed in `steamapps/libraryfolders.vdf`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SteamLibrary {
+    pub path: PathBuf,
+    pub apps: Vec<String>,
+}
+
+impl SteamLibrary {
+    fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            apps: Vec::new(),
+        }
+    }
+
+    pub fn has_app(&self, app_id: &str) -> bool {
+        // Old-style library files don't list apps, so also look for the manifest
+        self.apps.iter().any(|a| a == app_id)
+            || self
+                .path
+                .join("steamapps")
+                .join(format!("appmanifest_{app_id}.acf"))
+                .exists()
+    }
+
+    pub fn compatdata_path(&self, app_id: &str) -> PathBuf {
+        self.path.join("steamapps/compatdata").join(app_id)
+    }
+
+    /// Where the CA launcher keeps its mod file inside this library's Proton prefix.
+    pub fn proton_mod_file_path(&self) -> PathBuf {
+        self.compatdata_path(TWWH3_APP_ID)
+            .join("pfx/drive_c/users/steamuser")
+            .join(CA_LAUNCHER_DIR)
+            .join(CA_MOD_FILE)
+    }
+}
+
+/// A workshop item, as recorded in `steamapps/workshop/appworkshop_<app id>.acf`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkshopItem {
+    pub id: String,
+    /// Bytes on disk.
+    pub size: Option<u64>,
+    /// When the version on disk was published.
+    pub updated: Option<DateTime<Utc>>,
+}
+
+/// Reads the workshop items from a workshop manifest.
+pub fn read_workshop_manifest(path: &Path) -> Result<Vec<WorkshopItem>> {
+    let data = fs::read_to_string(path).context(format!("Could not read {path:?}"))?;
+    parse_workshop_manifest(&data).context(format!("Could not parse {path:?}"))
+}
+
+pub fn parse_workshop_manifest(data: &str) -> Result<Vec<WorkshopItem>> {
+    let doc = vdf::parse(data)?;
+    let manifest = doc
+        .get_object("AppWorkshop")
+        .context("Not a workshop manifest")?;
+    // Installed is what's on disk; details can be newer while an update is pending
+    let details = manifest.get_object("WorkshopItemDetails");
+    let Some(installed) = manifest.get_object("WorkshopItemsInstalled") else {
+        return Ok(vec![]);
+    };
+    Ok(installed
+        .iter()
+        .filter_map(|(id, item)| {
+            let item = item.as_object()?;
+            let detail = |key| details?.get_object(id)?.get_str(key);
+            let updated = item
+                .get_str("timeupdated")
+                .or_else(|| detail("timeupdated"));
+            Some(WorkshopItem {
+                id: id.to_string(),
+                size: item.get_str("size").and_then(|s| s.parse().ok()),
+                updated: updated
+                    .and_then(|t| t.parse().ok())
+                    .and_then(|t| Utc.timestamp_opt(t, 0).single()),
+            })
+        })
+        .collect())
+}
+
+/// The workshop items of every workshop mod in `mod_list`, by workshop id.
+///
+/// Manifests are looked up next to the mods' pack files, so this works for
+/// whichever library they are in. Manifests that can't be read are skipped.
+pub fn read_workshop_items(mod_list: &ModList) -> HashMap<String, WorkshopItem> {
+    let mut manifests: Vec<PathBuf> = mod_list
+        .mods()
+        .into_iter()
+        .filter(|m| m.workshop_id().is_some())
+        .filter_map(|m| {
+            // .../steamapps/workshop/content/<app id>/<workshop id>/<pack>
+            let app_dir = m.packfile_path().parent()?.parent()?;
+            let app_id = app_dir.file_name()?.to_str()?;
+            let workshop_dir = app_dir.parent()?.parent()?;
+            Some(workshop_dir.join(format!("appworkshop_{app_id}.acf")))
+        })
+        .collect();
+    manifests.sort();
+    manifests.dedup();
+    manifests
+        .iter()
+        .filter_map(|p| read_workshop_manifest(p).ok())
+        .flatten()
+        .map(|item| (item.id.clone(), item))
+        .collect()
+}
+
+/// Reads every library folder known to the Steam install at `steam_root`.
+///
+/// The root itself is always included, as older library files leave it out.
+pub fn read_library_folders(steam_root: &Path) -> Result<Vec<SteamLibrary>> {
+    let path = steam_root.join("steamapps/libraryfolders.vdf");
+    let data = fs::read_to_string(&path).context(format!("Could not read {path:?}"))?;
+    let doc = vdf::parse(&data).context(format!("Could not parse {path:?}"))?;
+    let folders = doc
+        .get_object("libraryfolders")
+        .context(format!("No library folders in {path:?}"))?;
+
+    let mut libraries: Vec<SteamLibrary> = folders
+        .iter()
+        // Skips "contentstatsid" and friends; only numbered keys are libraries
+        .filter(|(k, _)| k.parse::<usize>().is_ok())
+        .filter_map(|(_, v)| match v {
+            // Old format: "1" "/path/to/library"
+            vdf::VdfValue::String(p) => Some(SteamLibrary::new(PathBuf::from(p))),
+            vdf::VdfValue::Object(o) => o.get_str("path").map(|p| SteamLibrary {
+                path: PathBuf::from(p),
+                apps: o
+                    .get_object("apps")
+                    .map(|a| a.iter().map(|(id, _)| id.to_string()).collect())
+                    .unwrap_or_default(),
+            }),
+        })
+        .collect();
+
+    if !libraries.iter().any(|l| same_dir(&l.path, steam_root)) {
+        libraries.insert(0, SteamLibrary::new(steam_root.to_path_buf()));
+    }
+    Ok(libraries)
+}
+
+/// Finds the library under `steam_root` that has `app_id` installed.
+pub fn find_app_library(steam_root: &Path, app_id: &str) -> Result<Option<SteamLibrary>> {
+    Ok(read_library_folders(steam_root)?
+        .into_iter()
+        .find(|l| l.has_app(app_id)))
+}
+
+fn same_dir(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::Path};
+
+    use chrono::{TimeZone, Utc};
+
+    use super::{
+        find_app_library, parse_workshop_manifest, read_library_folders, read_workshop_items,
+        WorkshopItem, TWWH3_APP_ID,
+    };
+    use crate::mod_data::{ModFileDTO, ModList};
+
+    fn write_library_folders(steam_root: &Path, contents: &str) {
+        fs::create_dir_all(steam_root.join("steamapps")).unwrap();
+        fs::write(steam_root.join("steamapps/libraryfolders.vdf"), contents).unwrap();
+    }
+
+    fn escaped(path: &Path) -> String {
+        path.display().to_string().replace('\\', "\\\\")
+    }
+
+    #[test]
+    fn finds_game_on_secondary_library() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("Steam");
+        let second = dir.path().join("mnt/games/SteamLibrary");
+        write_library_folders(
+            &root,
+            &format!(
+                r#""libraryfolders"
+{{
+	"0"
+	{{
+		"path"		"{}"
+		"apps"
+		{{
+			"228980"		"0"
+		}}
+	}}
+	"1"
+	{{
+		"path"		"{}"
+		"apps"
+		{{
+			"1142710"		"123456"
+		}}
+	}}
+}}"#,
+                escaped(&root),
+                escaped(&second)
+            ),
+        );
+
+        let library = find_app_library(&root, TWWH3_APP_ID).unwrap().unwrap();
+        assert_eq!(second, library.path);
+        assert_eq!(
+            second.join(
+                "steamapps/compatdata/1142710/pfx/drive_c/users/steamuser/AppData/Roaming/The Creative Assembly/Launcher/20190104-moddata.dat"
+            ),
+            library.proton_mod_file_path()
+        );
+    }
+
+    #[test]
+    fn reads_old_format_and_includes_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("Steam");
+        let second = dir.path().join("SteamLibrary");
+        write_library_folders(
+            &root,
+            &format!(
+                "\"LibraryFolders\"\n{{\n\t\"TimeNextStatsReport\"\t\"1\"\n\t\"1\"\t\"{}\"\n}}\n",
+                escaped(&second)
+            ),
+        );
+        fs::create_dir_all(second.join("steamapps")).unwrap();
+        fs::write(second.join("steamapps/appmanifest_1142710.acf"), "").unwrap();
+
+        let libraries = read_library_folders(&root).unwrap();
+        assert_eq!(2, libraries.len());
+        assert_eq!(root, libraries[0].path);
+
+        let library = find_app_library(&root, TWWH3_APP_ID).unwrap().unwrap();
+        assert_eq!(second, library.path);
+    }
+
+    #[test]
+    fn missing_game_is_none() {
+        let dir = tempfile::tempdir().unwrap();
+        write_library_folders(dir.path(), "\"libraryfolders\" {}");
+        assert_eq!(None, find_app_library(dir.path(), TWWH3_APP_ID).unwrap());
+    }
+
+    const WORKSHOP_MANIFEST: &str = r#""AppWorkshop"
+{
+	"appid"		"1142710"
+	"SizeOnDisk"		"1234567"
+	"WorkshopItemsInstalled"
+	{
+		"2789857593"
+		{
+			"size"		"1234567"
+			"timeupdated"		"1700000000"
+			"manifest"		"5555"
+		}
+		"42"
+		{
+			"manifest"		"6666"
+		}
+	}
+	"WorkshopItemDetails"
+	{
+		"42"
+		{
+			"manifest"		"6666"
+			"timeupdated"		"1600000000"
+		}
+	}
+}"#;
+
+    #[test]
+    fn reads_workshop_items() {
+        let items = parse_workshop_manifest(WORKSHOP_MANIFEST).unwrap();
+        assert_eq!(
+            vec![
+                WorkshopItem {
+                    id: "2789857593".to_string(),
+                    size: Some(1234567),
+                    updated: Utc.timestamp_opt(1_700_000_000, 0).single(),
+                },
+                WorkshopItem {
+                    id: "42".to_string(),
+                    size: None,
+                    updated: Utc.timestamp_opt(1_600_000_000, 0).single(),
+                },
+            ],
+            items
+        );
+        assert!(parse_workshop_manifest(r#""libraryfolders" {}"#).is_err());
+    }
+
+    #[test]
+    fn finds_manifests_next_to_workshop_packs() {
+        let dir = tempfile::tempdir().unwrap();
+        let workshop = dir.path().join("steamapps/workshop");
+        fs::create_dir_all(&workshop).unwrap();
+        fs::write(workshop.join("appworkshop_1142710.acf"), WORKSHOP_MANIFEST).unwrap();
+        let fixture = include_str!("../fixtures/20190104-moddata.dat")
+            .replace("/home/gork/.local/share/Steam", &escaped(dir.path()));
+        let mod_list: ModList = serde_json::from_str::<ModFileDTO>(&fixture).unwrap().into();
+
+        let items = read_workshop_items(&mod_list);
+        assert_eq!(Some(1234567), items["2789857593"].size);
+        assert_eq!(2, items.len());
+    }
+}
@@ -0,0 +1,236 @@
+use std::{iter::Peekable, str::Chars};
+
+use anyhow::{anyhow, bail, Result};
+
+/// A value in Valve's KeyValues text format, as used by `.vdf` and `.acf` files.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VdfValue {
+    String(String),
+    Object(VdfObject),
+}
+
+impl VdfValue {
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            VdfValue::String(s) => Some(s),
+            VdfValue::Object(_) => None,
+        }
+    }
+
+    pub fn as_object(&self) -> Option<&VdfObject> {
+        match self {
+            VdfValue::String(_) => None,
+            VdfValue::Object(o) => Some(o),
+        }
+    }
+}
+
+/// Key/value pairs in file order. Keys may repeat, and lookups are
+/// case-insensitive like they are in Steam itself.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VdfObject(pub Vec<(String, VdfValue)>);
+
+impl VdfObject {
+    pub fn get(&self, key: &str) -> Option<&VdfValue> {
+        self.0
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| v)
+    }
+
+    pub fn get_str(&self, key: &str) -> Option<&str> {
+        self.get(key).and_then(VdfValue::as_str)
+    }
+
+    pub fn get_object(&self, key: &str) -> Option<&VdfObject> {
+        self.get(key).and_then(VdfValue::as_object)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &VdfValue)> {
+        self.0.iter().map(|(k, v)| (k.as_str(), v))
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Token {
+    Open,
+    Close,
+    Str(String),
+}
+
+struct Parser<'a> {
+    chars: Peekable<Chars<'a>>,
+    line: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(input: &'a str) -> Self {
+        Self {
+            chars: input.chars().peekable(),
+            line: 1,
+        }
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.chars.next();
+        if c == Some('\n') {
+            self.line += 1;
+        }
+        c
+    }
+
+    fn skip_whitespace_and_comments(&mut self) {
+        while let Some(&c) = self.chars.peek() {
+            if c.is_whitespace() {
+                self.bump();
+            } else if c == '/' && self.chars.clone().nth(1) == Some('/') {
+                while let Some(c) = self.bump() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn next_token(&mut self) -> Result<Option<Token>> {
+        loop {
+            self.skip_whitespace_and_comments();
+            let Some(&c) = self.chars.peek() else {
+                return Ok(None);
+            };
+            match c {
+                '{' => {
+                    self.bump();
+                    return Ok(Some(Token::Open));
+                }
+                '}' => {
+                    self.bump();
+                    return Ok(Some(Token::Close));
+                }
+                '"' => {
+                    self.bump();
+                    return self.quoted().map(|s| Some(Token::Str(s)));
+                }
+                '[' => {
+                    // Platform conditionals like [$WIN32]; we don't evaluate them.
+                    while let Some(c) = self.bump() {
+                        if c == ']' {
+                            break;
+                        }
+                    }
+                }
+                _ => return Ok(Some(Token::Str(self.unquoted()))),
+            }
+        }
+    }
+
+    fn quoted(&mut self) -> Result<String> {
+        let start = self.line;
+        let mut s = String::new();
+        loop {
+            match self.bump() {
+                Some('"') => return Ok(s),
+                Some('\\') => match self.bump() {
+                    Some('n') => s.push('\n'),
+                    Some('t') => s.push('\t'),
+                    Some(c) => s.push(c),
+                    None => break,
+                },
+                Some(c) => s.push(c),
+                None => break,
+            }
+        }
+        bail!("Unterminated string starting on line {start}")
+    }
+
+    fn unquoted(&mut self) -> String {
+        let mut s = String::new();
+        while let Some(&c) = self.chars.peek() {
+            if c.is_whitespace() || matches!(c, '{' | '}' | '"') {
+                break;
+            }
+            s.push(c);
+            self.bump();
+        }
+        s
+    }
+
+    fn object(&mut self, nested: bool) -> Result<VdfObject> {
+        let mut object = VdfObject::default();
+        loop {
+            let key = match self.next_token()? {
+                Some(Token::Str(key)) => key,
+                Some(Token::Close) if nested => return Ok(object),
+                None if !nested => return Ok(object),
+                Some(Token::Close) => bail!("Unexpected '}}' on line {}", self.line),
+                Some(Token::Open) => bail!("Unexpected '{{' on line {}", self.line),
+                None => bail!("Unexpected end of file, missing '}}'"),
+            };
+            let value = match self.next_token()? {
+                Some(Token::Str(s)) => VdfValue::String(s),
+                Some(Token::Open) => VdfValue::Object(self.object(true)?),
+                Some(Token::Close) | None => {
+                    return Err(anyhow!(
+                        "Missing value for key \"{key}\" on line {}",
+                        self.line
+                    ))
+                }
+            };
+            object.0.push((key, value));
+        }
+    }
+}
+
+/// Parses a KeyValues text document into its root object.
+pub fn parse(input: &str) -> Result<VdfObject> {
+    Parser::new(input).object(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, VdfValue};
+
+    #[test]
+    fn parses_nested_objects() {
+        let doc = parse(
+            r#"
+            // a comment
+            "libraryfolders"
+            {
+                "0"
+                {
+                    "path"		"C:\\Program Files (x86)\\Steam"
+                    "apps"
+                    {
+                        "1142710"		"123"
+                    }
+                }
+                unquoted value [$WIN32]
+            }
+            "#,
+        )
+        .unwrap();
+
+        let folders = doc.get_object("LibraryFolders").unwrap();
+        let zero = folders.get_object("0").unwrap();
+        assert_eq!(Some(r"C:\Program Files (x86)\Steam"), zero.get_str("path"));
+        assert_eq!(
+            Some("123"),
+            zero.get_object("apps").unwrap().get_str("1142710")
+        );
+        assert_eq!(
+            Some(&VdfValue::String("value".to_string())),
+            folders.get("unquoted")
+        );
+    }
+
+    #[test]
+    fn rejects_unbalanced_braces() {
+        assert!(parse(r#""a" { "b" "c""#).is_err());
+        assert!(parse(r#""a" "b" }"#).is_err());
+        assert!(parse(r#""a""#).is_err());
+    }
+}
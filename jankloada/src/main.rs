@@ -1,10 +1,27 @@
-use anyhow::Result;
-use iced::widget::{button, column, container, row, scrollable, text, text_input, toggler};
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Local, Utc};
+use iced::widget::{
+    button, checkbox, column, container, image, pick_list, row, scrollable, text, text_input,
+    toggler, tooltip, Space,
+};
 use iced::{
-    executor, theme, Alignment, Application, Color, Command, Element, Length, Settings, Theme,
+    clipboard, executor, theme, Alignment, Application, Color, Command, Element, Length, Settings,
+    Theme,
 };
+use jankloada_lib::conflicts::{find_conflicts, Conflict};
 use jankloada_lib::data_manager::DataManager;
-use jankloada_lib::mod_data::{ModEntry, ModList, ModProfile};
+use jankloada_lib::diff::ModListDiff;
+use jankloada_lib::history::{undo_target, Snapshot, SnapshotOrigin};
+use jankloada_lib::mod_data::{
+    ApplyReport, MatchRule, ModEntry, ModList, ModProfile, ModUUID, ProfileMod,
+};
+use jankloada_lib::pack::{file_tree, PackIndex};
+use jankloada_lib::steam::{read_workshop_items, WorkshopItem};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
 
 fn main() -> Result<()> {
     Jankloada::run(Settings::default())?;
@@ -17,7 +34,72 @@ struct Jankloada {
     mod_list: Option<ModList>,
     profile_list: Vec<String>,
     profile_name: String,
+    /// The last profile loaded or saved.
+    profile: Option<ModProfile>,
     dirty: bool,
+    /// What the next save will be recorded as in the history.
+    pending_origin: SnapshotOrigin,
+    history: Vec<Snapshot>,
+    /// Snapshot files that couldn't be read, and why.
+    unreadable_history: Vec<(PathBuf, String)>,
+    /// What saving would change, shown for confirmation before it happens.
+    preview: Option<ModListDiff>,
+    /// What didn't go to plan when the last profile was loaded.
+    apply_report: Option<ApplyReport>,
+    /// Why the last save of the mod list didn't work, if it didn't.
+    save_error: Option<String>,
+    /// How the last share code or mod file import went.
+    share_status: Option<String>,
+    /// Path to someone else's mod file, to make a profile of.
+    import_path: String,
+    /// A profile made from someone else's mod file, and the mods in it we
+    /// don't have, waiting to be saved.
+    pending_import: Option<(ModProfile, Vec<ProfileMod>)>,
+    /// File indexes of the packs of this game's mods, read after the mod list is.
+    pack_indexes: HashMap<ModUUID, PackIndex>,
+    /// Packs that couldn't be read, and why. Their mods aren't checked for conflicts.
+    pack_errors: HashMap<ModUUID, String>,
+    /// Files shipped by more than one active mod, as the mod list is now.
+    conflicts: Vec<Conflict>,
+    /// Indices into `conflicts` of the ones each mod is part of.
+    conflicts_by_mod: HashMap<ModUUID, Vec<usize>>,
+    /// The mod whose details are shown.
+    selected: Option<ModUUID>,
+    /// Only list files in the selected mod's pack whose path contains this.
+    pack_search: String,
+    /// Size and last change of the selected mod's pack, looked up when it's selected.
+    pack_metadata: (String, String),
+    /// The files in the selected mod's pack that match `pack_search`, as a tree.
+    pack_listing: String,
+    /// Preview pictures of the mods, `None` while loading or if there isn't one.
+    thumbnails: HashMap<ModUUID, Option<image::Handle>>,
+    /// What Steam knows about the workshop mods, by workshop id.
+    workshop: HashMap<String, WorkshopItem>,
+    list_order: ListOrder,
+    /// Only list mods from the Steam workshop.
+    workshop_only: bool,
+}
+
+/// How the mod list is sorted on screen. Mods can only be moved in load order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ListOrder {
+    LoadOrder,
+    Name,
+    Updated,
+}
+
+impl ListOrder {
+    const ALL: [ListOrder; 3] = [ListOrder::LoadOrder, ListOrder::Name, ListOrder::Updated];
+}
+
+impl fmt::Display for ListOrder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ListOrder::LoadOrder => write!(f, "Load order"),
+            ListOrder::Name => write!(f, "Name"),
+            ListOrder::Updated => write!(f, "Newest on da workshop"),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -27,9 +109,28 @@ enum Message {
     ListProfiles,
     NameProfile(String),
     DeleteProfile(String),
+    CopyShareCode(String),
+    PasteShareCode,
+    ImportShareCode(Option<String>),
+    NameImportPath(String),
+    PreviewImport,
+    SaveImport,
+    CancelImport,
     LoadModList,
+    PreviewSave,
     SaveModList,
+    CancelSave,
     ToggleModActive(usize, bool),
+    MoveMod { from: usize, to: usize },
+    SelectMod(Option<ModUUID>),
+    SearchPack(String),
+    ThumbnailLoaded(ModUUID, Option<Vec<u8>>),
+    PacksRead(Vec<(ModUUID, Result<PackIndex, String>)>),
+    SortList(ListOrder),
+    ShowWorkshopOnly(bool),
+    RemoveMod(usize),
+    Undo,
+    RestoreSnapshot(u64),
 }
 
 impl Application for Jankloada {
@@ -43,13 +144,40 @@ impl Application for Jankloada {
         let profile_list = data_manager
             .list_profiles()
             .expect("Failed to read profile dir");
+        let profile_name = data_manager
+            .settings()
+            .preferred_profile
+            .clone()
+            .unwrap_or_default();
         (
             Self {
                 data_manager,
                 mod_list: None,
-                profile_name: "".to_string(),
+                profile_name,
                 profile_list,
+                profile: None,
                 dirty: false,
+                pending_origin: SnapshotOrigin::Edit,
+                history: vec![],
+                unreadable_history: vec![],
+                preview: None,
+                apply_report: None,
+                save_error: None,
+                share_status: None,
+                import_path: String::new(),
+                pending_import: None,
+                pack_indexes: HashMap::new(),
+                pack_errors: HashMap::new(),
+                conflicts: vec![],
+                conflicts_by_mod: HashMap::new(),
+                selected: None,
+                pack_search: String::new(),
+                pack_metadata: Default::default(),
+                pack_listing: String::new(),
+                thumbnails: HashMap::new(),
+                workshop: HashMap::new(),
+                list_order: ListOrder::LoadOrder,
+                workshop_only: false,
             },
             Command::none(),
         )
@@ -66,10 +194,14 @@ impl Application for Jankloada {
                     .data_manager
                     .load_profile(n.clone())
                     .expect("Failed to load profile!");
-                self.mod_list
+                let loosest = self.data_manager.settings().match_strictness();
+                self.apply_report = self
+                    .mod_list
                     .as_mut()
-                    .map(|l| l.apply_profile(profile))
-                    .expect("Failed to apply profile!");
+                    .map(|l| l.apply_profile(profile.clone(), loosest));
+                self.profile = Some(profile);
+                self.refresh_conflicts();
+                self.pending_origin = SnapshotOrigin::Profile { name: n.clone() };
                 self.profile_name = n;
                 self.dirty = true;
             }
@@ -77,16 +209,21 @@ impl Application for Jankloada {
                 self.profile_name = s;
             }
             Message::SaveProfileAs(n) => {
-                let profile = ModProfile::new_from_mod_list(
-                    n,
-                    &self
-                        .mod_list
-                        .clone()
-                        .expect("Failed to read mod list, even though we already have it?"),
+                let mod_list = self
+                    .mod_list
+                    .as_ref()
+                    .expect("Failed to read mod list, even though we already have it?");
+                let game = self.data_manager.settings().game();
+                // Overwriting the loaded profile keeps its description, tags and notes
+                let profile = match self.profile.take() {
+                    Some(p) if p.name == n => p.with_mods_from(mod_list, game),
+                    _ => ModProfile::new_from_mod_list(n, mod_list, game),
+                };
+                self.profile = Some(
+                    self.data_manager
+                        .save_profile(profile)
+                        .expect("Failed to save mod profile!"),
                 );
-                self.data_manager
-                    .save_profile(profile)
-                    .expect("Failed to save mod profile!");
                 self.reload_profile_list()
                     .expect("Failed to reload profile list");
             }
@@ -97,6 +234,86 @@ impl Application for Jankloada {
                 self.reload_profile_list()
                     .expect("Failed to reload profile list");
             }
+            Message::CopyShareCode(n) => {
+                let code = self
+                    .data_manager
+                    .load_profile(n.clone())
+                    .and_then(|p| p.to_share_code());
+                match code {
+                    Ok(code) => {
+                        self.share_status = Some(format!("Copied {n}, go paste it to da boyz"));
+                        return clipboard::write(code);
+                    }
+                    Err(e) => self.share_status = Some(format!("Couldn't copy {n}: {e}")),
+                }
+            }
+            Message::PasteShareCode => {
+                return clipboard::read(Message::ImportShareCode);
+            }
+            Message::ImportShareCode(code) => {
+                let profile = code
+                    .ok_or_else(|| anyhow!("Nuffin' in da clipboard"))
+                    .and_then(|code| ModProfile::from_share_code(&code));
+                self.share_status = Some(match profile.and_then(|p| self.import_profile(p)) {
+                    Ok(name) => format!("Nicked {name}"),
+                    Err(e) => format!("Couldn't nick it: {e}"),
+                });
+            }
+            Message::NameImportPath(s) => {
+                self.import_path = s;
+            }
+            Message::PreviewImport => {
+                let Some(mod_list) = &self.mod_list else {
+                    self.share_status =
+                        Some("Load yer mods first, nuffin' to check it against".to_string());
+                    return Command::none();
+                };
+                match DataManager::read_mod_file(Path::new(&self.import_path)) {
+                    Ok(theirs) => {
+                        let profile = ModProfile::new_from_mod_list(
+                            self.profile_name.clone(),
+                            &theirs,
+                            self.data_manager.settings().game(),
+                        );
+                        let loosest = self.data_manager.settings().match_strictness();
+                        let found = mod_list.match_profile(
+                            &profile.active_mods,
+                            profile.game.as_deref(),
+                            loosest,
+                        );
+                        let missing = profile
+                            .active_mods
+                            .iter()
+                            .zip(found)
+                            .filter(|(_, found)| found.is_none())
+                            .map(|(m, _)| m.clone())
+                            .collect();
+                        self.share_status = None;
+                        self.pending_import = Some((profile, missing));
+                    }
+                    Err(e) => self.share_status = Some(format!("Couldn't read dat: {e}")),
+                }
+            }
+            Message::SaveImport => {
+                if let Some((profile, missing)) = self.pending_import.take() {
+                    let name = profile.name.clone();
+                    self.share_status =
+                        Some(match self.data_manager.save_profile(profile.clone()) {
+                            Ok(_) => {
+                                self.reload_profile_list()
+                                    .expect("Failed to reload profile list");
+                                format!("Nicked {name}")
+                            }
+                            Err(e) => {
+                                self.pending_import = Some((profile, missing));
+                                format!("Couldn't save {name}: {e}")
+                            }
+                        });
+                }
+            }
+            Message::CancelImport => {
+                self.pending_import = None;
+            }
             Message::ListProfiles => {
                 self.reload_profile_list()
                     .expect("Failed to reload profile list");
@@ -104,27 +321,126 @@ impl Application for Jankloada {
             Message::LoadModList => {
                 // TODO: Needs to initialize this safer, warn etc
                 let manager = self.data_manager.load_mod_file().unwrap();
+                self.pack_indexes.clear();
+                self.pack_errors.clear();
+                self.workshop = read_workshop_items(&manager);
                 self.mod_list = Some(manager);
+                self.refresh_pack_metadata();
+                self.refresh_conflicts();
                 self.profile_name = "".to_string();
+                self.profile = None;
+                self.apply_report = None;
                 self.dirty = false;
+                self.reload_history();
+                return Command::batch([self.read_packs(), self.load_thumbnails()]);
+            }
+            Message::PreviewSave => {
+                let on_disk = self
+                    .data_manager
+                    .load_mod_file()
+                    .expect("Failed to read mod file!");
+                if let Some(ml) = &self.mod_list {
+                    let mut diff = ModListDiff::new(&on_disk, ml);
+                    if let (SnapshotOrigin::Profile { .. }, Some(report)) =
+                        (&self.pending_origin, &self.apply_report)
+                    {
+                        diff.not_installed = report.not_found.clone();
+                    }
+                    self.preview = Some(diff);
+                }
+            }
+            Message::CancelSave => {
+                self.preview = None;
             }
             Message::SaveModList => {
-                self.mod_list
-                    .as_ref()
-                    .map(|ml| self.data_manager.save_to_mod_file(ml.clone()));
-                self.dirty = false;
+                self.preview = None;
+                let Some(ml) = &self.mod_list else {
+                    return Command::none();
+                };
+                let origin = self.pending_origin.clone();
+                match self.data_manager.save_to_mod_file(ml.clone(), origin) {
+                    Ok(_) => {
+                        self.save_error = None;
+                        self.dirty = false;
+                        self.pending_origin = SnapshotOrigin::Edit;
+                    }
+                    Err(e) => self.save_error = Some(format!("Couldn't save it: {e}")),
+                }
+                self.reload_history();
             }
             Message::ToggleModActive(i, b) => {
                 self.mod_list
                     .as_mut()
                     .map(|ml| ml.set_mod_active_state(i, b));
+                self.refresh_conflicts();
+                self.pending_origin = SnapshotOrigin::Edit;
+                self.dirty = true;
+            }
+            Message::MoveMod { from, to } => {
+                self.mod_list
+                    .as_mut()
+                    .map(|ml| ml.move_mod(from, to))
+                    .transpose()
+                    .expect("Failed to move mod!");
+                self.refresh_conflicts();
+                self.pending_origin = SnapshotOrigin::Edit;
+                self.dirty = true;
+            }
+            Message::SelectMod(uuid) => {
+                self.selected = uuid;
+                self.pack_search.clear();
+                self.refresh_pack_metadata();
+                self.refresh_pack_listing();
+            }
+            Message::SearchPack(s) => {
+                self.pack_search = s;
+                self.refresh_pack_listing();
+            }
+            Message::SortList(order) => {
+                self.list_order = order;
+            }
+            Message::ShowWorkshopOnly(b) => {
+                self.workshop_only = b;
+            }
+            Message::ThumbnailLoaded(uuid, bytes) => {
+                self.thumbnails
+                    .insert(uuid, bytes.map(image::Handle::from_memory));
+            }
+            Message::PacksRead(packs) => {
+                for (uuid, index) in packs {
+                    match index {
+                        Ok(index) => {
+                            self.pack_indexes.insert(uuid, index);
+                        }
+                        Err(e) => {
+                            self.pack_errors.insert(uuid, e);
+                        }
+                    }
+                }
+                self.refresh_conflicts();
+                self.refresh_pack_listing();
+            }
+            Message::RemoveMod(i) => {
+                self.mod_list.as_mut().map(|ml| ml.remove_mod(i));
+                self.refresh_conflicts();
+                self.pending_origin = SnapshotOrigin::Prune;
                 self.dirty = true;
             }
+            Message::Undo => {
+                self.data_manager.undo().expect("Failed to undo!");
+                return self.update(Message::LoadModList);
+            }
+            Message::RestoreSnapshot(id) => {
+                self.data_manager
+                    .restore_snapshot(id)
+                    .expect("Failed to restore snapshot!");
+                return self.update(Message::LoadModList);
+            }
         };
         Command::none()
     }
 
-    fn view(&self) -> Element<Message> {
+    fn view(&self) -> Element<'_, Message> {
         let load_button = button(if self.mod_list.is_none() {
             "'Ave a look"
         } else {
@@ -133,15 +449,18 @@ impl Application for Jankloada {
         .on_press(Message::LoadModList);
         let mut buttons = row![load_button].spacing(20);
         if self.mod_list.is_some() {
-            let text = text("Make it like dis now");
-            let save_b = button(text)
-                .on_press(Message::SaveModList)
+            let save_b = button(text("Make it like dis now"))
+                .on_press(Message::PreviewSave)
                 .style(if self.dirty {
                     theme::Button::Destructive
                 } else {
                     theme::Button::Secondary
                 });
             buttons = buttons.push(save_b);
+            if let Some(e) = &self.save_error {
+                buttons =
+                    buttons.push(text(e).style(theme::Text::Color(Color::from_rgb8(255, 165, 0))));
+            }
         };
         let mut contents = column![buttons].padding(20).align_items(Alignment::Start);
         if self.mod_list.is_some() {
@@ -162,7 +481,10 @@ impl Application for Jankloada {
             );
         }
 
-        let main_panel = self.view_main_overview();
+        let main_panel = match &self.preview {
+            Some(diff) => view_preview(diff),
+            None => self.view_main_overview(),
+        };
         contents = contents.push(main_panel);
         let contents = contents;
         container(contents).into()
@@ -175,31 +497,253 @@ impl Jankloada {
         Ok(())
     }
 
-    fn view_main_overview(&self) -> Element<Message> {
+    /// Saves a profile from somewhere else, renaming it rather than
+    /// overwriting one we've already got.
+    fn import_profile(&mut self, mut profile: ModProfile) -> Result<String> {
+        let taken = self.data_manager.list_profiles()?;
+        let name = profile.name.clone();
+        let mut n = 1;
+        while taken.contains(&profile.name) {
+            n += 1;
+            profile.name = format!("{name} {n}");
+        }
+        let profile = self.data_manager.save_profile(profile)?;
+        self.reload_profile_list()?;
+        Ok(profile.name)
+    }
+
+    /// Starts reading the indexes of this game's packs, to look for conflicts in.
+    fn read_packs(&self) -> Command<Message> {
+        let Some(mod_list) = &self.mod_list else {
+            return Command::none();
+        };
+        let game = self.data_manager.settings().game();
+        let packs: Vec<_> = mod_list
+            .mods()
+            .into_iter()
+            .filter(|m| m.game == game)
+            .map(|m| (m.uuid.clone(), m.packfile_path().to_path_buf()))
+            .collect();
+        Command::perform(
+            async move {
+                packs
+                    .into_iter()
+                    .map(|(uuid, path)| {
+                        let index = PackIndex::from_path(&path).map_err(|e| format!("{e:#}"));
+                        (uuid, index)
+                    })
+                    .collect()
+            },
+            Message::PacksRead,
+        )
+    }
+
+    /// Starts loading the pictures of this game's mods that aren't loaded yet.
+    fn load_thumbnails(&mut self) -> Command<Message> {
+        let Some(mod_list) = &self.mod_list else {
+            return Command::none();
+        };
+        let game = self.data_manager.settings().game();
+        let commands: Vec<_> = mod_list
+            .mods()
+            .into_iter()
+            .filter(|m| m.game == game && !self.thumbnails.contains_key(&m.uuid))
+            .map(|m| {
+                let entry = m.clone();
+                Command::perform(
+                    async move {
+                        let bytes = entry.preview_image().and_then(|p| fs::read(p).ok());
+                        (entry.uuid, bytes)
+                    },
+                    |(uuid, bytes)| Message::ThumbnailLoaded(uuid, bytes),
+                )
+            })
+            .collect();
+        for m in mod_list.mods() {
+            self.thumbnails.entry(m.uuid.clone()).or_insert(None);
+        }
+        Command::batch(commands)
+    }
+
+    /// Compares the packs of the active mods, from the indexes read earlier.
+    fn refresh_conflicts(&mut self) {
+        let Some(mod_list) = &self.mod_list else {
+            return;
+        };
+        self.conflicts = find_conflicts(
+            mod_list
+                .get_active()
+                .into_iter()
+                .filter_map(|m| Some((m, self.pack_indexes.get(&m.uuid)?))),
+        );
+        self.conflicts_by_mod.clear();
+        for (i, conflict) in self.conflicts.iter().enumerate() {
+            for m in &conflict.mods {
+                self.conflicts_by_mod
+                    .entry(m.uuid.clone())
+                    .or_default()
+                    .push(i);
+            }
+        }
+    }
+
+    fn selected_mod(&self) -> Option<&ModEntry> {
+        let mod_list = self.mod_list.as_ref()?;
+        let uuid = self.selected.as_ref()?;
+        Some(mod_list.mods()[mod_list.index_of(uuid).ok()?])
+    }
+
+    /// Looks up the selected mod's pack on disk, once rather than every frame.
+    fn refresh_pack_metadata(&mut self) {
+        let Some(entry) = self.selected_mod() else {
+            return;
+        };
+        self.pack_metadata = match fs::metadata(entry.packfile_path()) {
+            Ok(meta) => (
+                human_size(meta.len()),
+                meta.modified()
+                    .map(|t| {
+                        DateTime::<Local>::from(t)
+                            .format("%Y-%m-%d %H:%M")
+                            .to_string()
+                    })
+                    .unwrap_or_default(),
+            ),
+            Err(_) => ("gone".to_string(), "".to_string()),
+        };
+    }
+
+    /// Lists the selected mod's pack, from the index read earlier.
+    fn refresh_pack_listing(&mut self) {
+        self.pack_listing.clear();
+        let Some(index) = self
+            .selected_mod()
+            .and_then(|m| self.pack_indexes.get(&m.uuid))
+        else {
+            return;
+        };
+        let query = self.pack_search.to_lowercase();
+        let tree = file_tree(
+            index
+                .files
+                .iter()
+                .filter(|f| f.path.to_lowercase().contains(&query)),
+        );
+        // Big packs have tens of thousands of files; nobody scrolls through all that
+        const MAX_LINES: usize = 500;
+        let mut lines: Vec<_> = tree
+            .iter()
+            .take(MAX_LINES)
+            .map(|l| {
+                let indent = "  ".repeat(l.depth);
+                match l.entry {
+                    Some(f) => format!("{indent}{} ({})", l.name, human_size(u64::from(f.size))),
+                    None => format!("{indent}{}/", l.name),
+                }
+            })
+            .collect();
+        if tree.len() > MAX_LINES {
+            lines.push(format!(
+                "...an' {} more, search fer 'em",
+                tree.len() - MAX_LINES
+            ));
+        }
+        self.pack_listing = lines.join("\n");
+    }
+
+    /// Why `uuid`'s pack wasn't checked for conflicts, if it wasn't.
+    fn unchecked_reason(&self, uuid: &ModUUID) -> Option<&str> {
+        if self.pack_indexes.contains_key(uuid) {
+            return None;
+        }
+        Some(
+            self.pack_errors
+                .get(uuid)
+                .map_or("still readin' it", String::as_str),
+        )
+    }
+
+    /// Shows whatever of the history can be read; it's never worth crashing over.
+    fn reload_history(&mut self) {
+        self.history = self.data_manager.history().unwrap_or_default();
+        self.unreadable_history = self.data_manager.unreadable_history().unwrap_or_default();
+    }
+
+    fn view_main_overview(&self) -> Element<'_, Message> {
         let mod_pane = scrollable(self.view_modlist()).height(Length::Fill);
         let profile_pane = if self.mod_list.is_some() {
             self.view_profiles()
         } else {
             text("").into()
         };
-        row![
-            column![mod_pane].width(Length::FillPortion(4)),
-            column![profile_pane].width(Length::FillPortion(1))
-        ]
-        .padding(20)
-        .spacing(20)
-        .into()
+        let mut panes = row![column![mod_pane].width(Length::FillPortion(4))]
+            .padding(20)
+            .spacing(20);
+        if let Some(entry) = self.selected_mod() {
+            panes = panes.push(column![self.view_mod_details(entry)].width(Length::FillPortion(2)));
+        }
+        panes
+            .push(column![profile_pane].width(Length::FillPortion(1)))
+            .into()
     }
 
-    fn view_modlist(&self) -> Element<Message> {
+    fn view_modlist(&self) -> Element<'_, Message> {
         let all_mods = self.mod_list.as_ref().map(|m| m.mods()).unwrap_or_default();
+        let workshop_item = |m: &ModEntry| m.workshop_id().and_then(|id| self.workshop.get(id));
+        // The launcher's mod file is shared between games
+        let mut shown: Vec<usize> = (0..all_mods.len())
+            .filter(|&i| all_mods[i].game == self.data_manager.settings().game())
+            .filter(|&i| !self.workshop_only || all_mods[i].workshop_id().is_some())
+            .collect();
+        match self.list_order {
+            ListOrder::LoadOrder => (),
+            ListOrder::Name => shown.sort_by_cached_key(|&i| all_mods[i].name.to_lowercase()),
+            ListOrder::Updated => shown.sort_by_key(|&i| {
+                let updated = workshop_item(all_mods[i]).and_then(|w| w.updated);
+                (updated.is_none(), Reverse(updated))
+            }),
+        }
+        let controls = row![
+            pick_list(
+                &ListOrder::ALL[..],
+                Some(self.list_order),
+                Message::SortList
+            ),
+            checkbox(
+                "Only workshop stuff",
+                self.workshop_only,
+                Message::ShowWorkshopOnly
+            ),
+        ]
+        .spacing(20)
+        .align_items(Alignment::Center);
         let list: Element<_> = column(
-            all_mods
+            shown
                 .iter()
-                // Hack to only show twwh3
                 .enumerate()
-                .filter(|(_, m)| m.game == "warhammer3")
-                .map(|(i, m)| view_mod_entry(i, m))
+                .map(|(n, &i)| {
+                    // Moving swaps places with the neighbour on screen, skipping other games' mods
+                    let movable = self.list_order == ListOrder::LoadOrder;
+                    let up = n.checked_sub(1).map(|p| shown[p]).filter(|_| movable);
+                    let down = shown.get(n + 1).copied().filter(|_| movable);
+                    let conflicts: Vec<_> = self
+                        .conflicts_by_mod
+                        .get(&all_mods[i].uuid)
+                        .into_iter()
+                        .flatten()
+                        .map(|&c| &self.conflicts[c])
+                        .collect();
+                    let conflicts = match self.unchecked_reason(&all_mods[i].uuid) {
+                        Some(reason) => Err(reason),
+                        None => Ok(conflicts.as_slice()),
+                    };
+                    let thumbnail = self
+                        .thumbnails
+                        .get(&all_mods[i].uuid)
+                        .and_then(Option::as_ref);
+                    let updated = workshop_item(all_mods[i]).and_then(|w| w.updated);
+                    view_mod_entry(i, all_mods[i], up, down, conflicts, thumbnail, updated)
+                })
                 .collect::<Vec<_>>(),
         )
         .padding(20)
@@ -207,10 +751,80 @@ impl Jankloada {
         .width(Length::Fill)
         .align_items(Alignment::End)
         .into();
-        list
+        column![controls, list].padding([0, 20]).into()
+    }
+
+    fn view_mod_details<'a>(&'a self, entry: &'a ModEntry) -> Element<'a, Message> {
+        let path = entry.packfile_path();
+        let (size, modified) = self.pack_metadata.clone();
+        let details = [
+            ("Uuid", entry.uuid.0.clone()),
+            ("Wot kind", entry.category.clone()),
+            (
+                "Got it",
+                if entry.owned { "yep" } else { "nope" }.to_string(),
+            ),
+            ("Pack", path.display().to_string()),
+            ("Size", size),
+            ("Last fiddled wiv", modified),
+        ];
+        let workshop = entry.workshop_id().map(|id| {
+            let item = self.workshop.get(id);
+            let updated = item
+                .and_then(|w| w.updated)
+                .map(|u| u.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string());
+            [
+                ("Workshop", id.to_string()),
+                ("Updated on da workshop", updated.unwrap_or_default()),
+                (
+                    "Size says Steam",
+                    item.and_then(|w| w.size)
+                        .map(human_size)
+                        .unwrap_or_default(),
+                ),
+            ]
+        });
+        let details = details
+            .iter()
+            .chain(workshop.iter().flatten())
+            .map(|(label, value)| format!("{label}: {value}"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let close = button("SHUT IT").on_press(Message::SelectMod(None));
+        let mut header = column![row![text(&entry.name).size(24).width(Length::Fill), close]];
+        if let Some(Some(handle)) = self.thumbnails.get(&entry.uuid) {
+            header = header.push(image(handle.clone()).width(Length::Fixed(256.0)));
+        }
+        let header = header.push(text(details).size(14)).spacing(10);
+
+        let Some(index) = self.pack_indexes.get(&entry.uuid) else {
+            let reason = self.unchecked_reason(&entry.uuid).unwrap_or_default();
+            return column![
+                header,
+                text(format!("Can't read wot's in dis pack: {reason}"))
+            ]
+            .spacing(20)
+            .into();
+        };
+        let search = text_input("Wot ya lookin' for?", &self.pack_search)
+            .on_input(Message::SearchPack)
+            .width(Length::Fill);
+        let summary = format!(
+            "{} files, {}",
+            index.files.len(),
+            human_size(index.data_size())
+        );
+        column![
+            header,
+            text(summary).size(14),
+            search,
+            scrollable(text(&self.pack_listing).size(14)).height(Length::Fill)
+        ]
+        .spacing(10)
+        .into()
     }
 
-    fn view_profiles(&self) -> Element<Message> {
+    fn view_profiles(&self) -> Element<'_, Message> {
         let save_current_button = if self.profile_name.is_empty() {
             button("SAVE DIS")
         } else {
@@ -220,6 +834,7 @@ impl Jankloada {
         let load_profiles_button = button("WOT")
             .on_press(Message::ListProfiles)
             .style(theme::Button::Positive);
+        let paste_button = button("NICK").on_press(Message::PasteShareCode);
         let profile_name_input = text_input("Ya needs ta NAME it!", &self.profile_name)
             .on_input(Message::NameProfile)
             .width(Length::Fill);
@@ -231,34 +846,343 @@ impl Jankloada {
                     let load = button(n.as_str())
                         .on_press(Message::LoadProfile(n.clone()))
                         .width(Length::Fill);
+                    let copy = button("GIB").on_press(Message::CopyShareCode(n.clone()));
                     let delete = button("KRUMP")
                         .on_press(Message::DeleteProfile(n.clone()))
                         .style(theme::Button::Destructive);
-                    row![load, delete]
+                    row![load, copy, delete]
                 }
                 .into()
             })
             .collect();
         column![
-            row![save_current_button, load_profiles_button],
+            row![save_current_button, load_profiles_button, paste_button],
             profile_name_input,
-            column(profile_list_rows).spacing(5)
+            self.view_import(),
+            text(self.share_status.as_deref().unwrap_or_default()).size(14),
+            column(profile_list_rows).spacing(5),
+            self.view_profile_details(),
+            self.view_apply_report(),
+            self.view_history()
         ]
         .spacing(20)
         .into()
     }
+
+    fn view_import(&self) -> Element<'_, Message> {
+        let Some((profile, missing)) = &self.pending_import else {
+            let path_input = text_input("Yer mate's moddata.dat", &self.import_path)
+                .on_input(Message::NameImportPath)
+                .width(Length::Fill);
+            let import_button = if self.profile_name.is_empty()
+                || self.import_path.is_empty()
+                || self.mod_list.is_none()
+            {
+                button("NICK DAT")
+            } else {
+                button("NICK DAT").on_press(Message::PreviewImport)
+            };
+            return row![path_input, import_button].into();
+        };
+        let mut lines = vec![format!(
+            "{} mods fer \"{}\"{}",
+            profile.active_mods.len(),
+            profile.name,
+            if self.profile_list.contains(&profile.name) {
+                ", KRUMPs da one ya got"
+            } else {
+                ""
+            }
+        )];
+        if !missing.is_empty() {
+            lines.push("Ain't got dese:".to_string());
+            lines.extend(missing.iter().map(|m| m.to_string()));
+        }
+        column![
+            text(lines.join("\n")).size(14),
+            row![
+                button("KEEP IT")
+                    .on_press(Message::SaveImport)
+                    .style(theme::Button::Positive),
+                button("NAH").on_press(Message::CancelImport)
+            ]
+            .spacing(5)
+        ]
+        .spacing(5)
+        .into()
+    }
+
+    fn view_profile_details(&self) -> Element<'_, Message> {
+        let Some(profile) = &self.profile else {
+            return column![].into();
+        };
+        let when = |t: DateTime<Utc>| t.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string();
+        let mut lines = vec![format!("Da \"{}\" lot", profile.name)];
+        lines.extend(profile.description.clone());
+        let details = [
+            ("Fer", profile.game.clone()),
+            ("Made by", profile.author.clone()),
+            (
+                "Tags",
+                Some(profile.tags.join(", ")).filter(|t| !t.is_empty()),
+            ),
+            ("Made", profile.created.map(when)),
+            ("Fiddled wiv", profile.updated.map(when)),
+        ];
+        lines.extend(
+            details
+                .into_iter()
+                .filter_map(|(label, value)| value.map(|v| format!("{label}: {v}"))),
+        );
+        lines.extend(
+            profile
+                .active_mods
+                .iter()
+                .filter_map(|m| m.note.as_ref().map(|note| format!("{m}: {note}"))),
+        );
+        text(lines.join("\n")).size(14).into()
+    }
+
+    fn view_apply_report(&self) -> Element<'_, Message> {
+        let Some(report) = &self.apply_report else {
+            return column![].into();
+        };
+        let sections = [
+            (
+                "Ain't got dese",
+                report
+                    .not_found
+                    .iter()
+                    .map(|m| m.to_string())
+                    .collect::<Vec<_>>(),
+            ),
+            (
+                "Found it anyway",
+                report
+                    .matched
+                    .iter()
+                    .filter(|m| m.rule != MatchRule::Uuid)
+                    .map(|m| format!("{} by {}", m.wanted, m.rule))
+                    .collect(),
+            ),
+            (
+                "Switched off",
+                report.deactivated.iter().map(|m| m.name.clone()).collect(),
+            ),
+            (
+                "Couldn't sort, stuck 'em at da end",
+                report.unordered.iter().map(|m| m.name.clone()).collect(),
+            ),
+        ];
+        column(
+            sections
+                .into_iter()
+                .filter(|(_, lines)| !lines.is_empty())
+                .map(|(title, lines)| {
+                    text(format!("{title}:\n{}", lines.join("\n")))
+                        .size(14)
+                        .into()
+                })
+                .collect(),
+        )
+        .spacing(10)
+        .into()
+    }
+
+    fn view_history(&self) -> Element<'_, Message> {
+        let undo_button = if undo_target(&self.history).is_some() {
+            button("OOPS").on_press(Message::Undo)
+        } else {
+            button("OOPS")
+        }
+        .style(theme::Button::Destructive)
+        .width(Length::Fill);
+        let timeline = self
+            .history
+            .iter()
+            .rev()
+            .map(|s| {
+                let when = s
+                    .created
+                    .with_timezone(&Local)
+                    .format("%a %H:%M")
+                    .to_string();
+                let restore = button("GO BAK").on_press(Message::RestoreSnapshot(s.id));
+                row![
+                    text(format!("#{} {when}\n{}", s.id, s.origin))
+                        .size(14)
+                        .width(Length::Fill),
+                    restore
+                ]
+                .spacing(5)
+                .align_items(Alignment::Center)
+                .into()
+            })
+            .collect();
+        let mut history = column![undo_button].spacing(10);
+        if !self.unreadable_history.is_empty() {
+            let lines: Vec<_> = self
+                .unreadable_history
+                .iter()
+                .map(|(path, reason)| format!("{}: {reason}", path.display()))
+                .collect();
+            history = history.push(tooltip(
+                text(format!(
+                    "{} snapshots got smashed, skippin' 'em",
+                    self.unreadable_history.len()
+                ))
+                .size(14)
+                .style(theme::Text::Color(Color::from_rgb8(255, 165, 0))),
+                lines.join("\n"),
+                tooltip::Position::Bottom,
+            ));
+        }
+        history
+            .push(scrollable(column(timeline).spacing(5)).height(Length::Fixed(300.0)))
+            .into()
+    }
+}
+
+fn view_preview(diff: &ModListDiff) -> Element<'_, Message> {
+    let names = |mods: &[ModEntry]| mods.iter().map(|m| m.name.clone()).collect::<Vec<_>>();
+    let sections = [
+        ("Switchin' on", names(&diff.activated)),
+        ("Switchin' off", names(&diff.deactivated)),
+        (
+            "Shovin' about",
+            diff.moved
+                .iter()
+                .map(|m| format!("{} ({} -> {})", m.entry.name, m.from + 1, m.to + 1))
+                .collect(),
+        ),
+        ("Chuckin' out", names(&diff.removed)),
+        ("New stuff", names(&diff.added)),
+        (
+            "Ain't got dese",
+            diff.not_installed.iter().map(|m| m.to_string()).collect(),
+        ),
+    ];
+    let mut changes = column![].spacing(15);
+    if diff.is_empty() {
+        changes = changes.push(text("Nuffin' changes."));
+    }
+    for (title, lines) in sections.into_iter().filter(|(_, l)| !l.is_empty()) {
+        let lines = lines.into_iter().map(|l| text(l).size(16).into()).collect();
+        changes = changes.push(column![text(title).size(22), column(lines).spacing(2)].spacing(5));
+    }
+    let buttons = row![
+        button("DO IT")
+            .on_press(Message::SaveModList)
+            .style(theme::Button::Destructive),
+        button("NAH").on_press(Message::CancelSave)
+    ]
+    .spacing(20);
+    column![scrollable(changes).height(Length::Fill), buttons]
+        .padding(20)
+        .spacing(20)
+        .into()
+}
+
+/// Bytes, in whichever unit keeps the number short.
+fn human_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{size:.1} {}", UNITS[unit])
+    }
 }
 
-fn view_mod_entry(i: usize, x: &ModEntry) -> Element<Message> {
+/// `conflicts` is the mod's conflicts, or why its pack couldn't be checked for any.
+fn view_mod_entry<'a>(
+    i: usize,
+    x: &'a ModEntry,
+    up: Option<usize>,
+    down: Option<usize>,
+    conflicts: Result<&[&Conflict], &str>,
+    thumbnail: Option<&image::Handle>,
+    updated: Option<DateTime<Utc>>,
+) -> Element<'a, Message> {
     let pri = text(i + 1);
-    let game = text(format!("({})", &x.game));
+    let move_button = |label, to: Option<usize>| {
+        let b = button(text(label).size(14)).style(theme::Button::Secondary);
+        match to {
+            Some(to) => b.on_press(Message::MoveMod { from: i, to }),
+            None => b,
+        }
+    };
+    let moves = row![move_button("UP", up), move_button("DOWN", down)].spacing(5);
+    let game = match updated {
+        Some(u) => text(format!(
+            "({}, updated {})",
+            &x.game,
+            u.with_timezone(&Local).format("%Y-%m-%d")
+        )),
+        None => text(format!("({})", &x.game)),
+    };
     let exists = x.file_exists();
     let active =
         toggler(None, x.active, move |b| Message::ToggleModActive(i, b)).width(Length::Shrink);
-    let name = text(&x.name).width(Length::Fill).style(if exists {
+    let name = button(text(&x.name).style(if exists {
         theme::Text::Default
     } else {
         theme::Text::Color(Color::from_rgb8(255, 165, 0))
-    });
-    row![pri, active, name, game].spacing(20).into()
+    }))
+    .on_press(Message::SelectMod(Some(x.uuid.clone())))
+    .style(theme::Button::Text)
+    .width(Length::Fill);
+    // Same size whether there's a picture or not, to keep the names lined up
+    let size = Length::Fixed(48.0);
+    let picture: Element<_> = match thumbnail {
+        Some(handle) => image(handle.clone()).width(size).height(size).into(),
+        None => Space::new(size, size).into(),
+    };
+    let mut entry = row![pri, moves, active, picture, name].spacing(20);
+    let orange = Color::from_rgb8(255, 165, 0);
+    let conflicts = match conflicts {
+        Ok(conflicts) => conflicts,
+        Err(reason) => {
+            let warning = text("??").style(theme::Text::Color(orange));
+            entry = entry.push(
+                tooltip(
+                    warning,
+                    format!("Not checked fer clashes: {reason}"),
+                    tooltip::Position::Left,
+                )
+                .style(theme::Container::Box),
+            );
+            &[]
+        }
+    };
+    if !conflicts.is_empty() {
+        let lines: Vec<_> = conflicts
+            .iter()
+            .map(|c| {
+                if c.winner().uuid == x.uuid {
+                    format!("{}: dis one wins", c.path)
+                } else {
+                    format!("{}: {} wins", c.path, c.winner().name)
+                }
+            })
+            .collect();
+        let warning = text(format!("!! {}", conflicts.len())).style(theme::Text::Color(orange));
+        entry = entry.push(
+            tooltip(warning, lines.join("\n"), tooltip::Position::Left)
+                .style(theme::Container::Box),
+        );
+    }
+    if !exists {
+        entry = entry.push(
+            button(text("YEET MISSIN'").size(14))
+                .on_press(Message::RemoveMod(i))
+                .style(theme::Button::Destructive),
+        );
+    }
+    entry.push(game).align_items(Alignment::Center).into()
 }